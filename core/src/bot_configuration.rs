@@ -1,10 +1,28 @@
-use crate::types::bot::{Automation, DelayConfig};
+use crate::types::bot::{
+    Automation, CollectStrategy, DelayConfig, KickRules, LeaveAction, LeaveRules,
+};
+use crate::types::disconnect::{DisconnectReason, ReconnectAction, ReconnectPolicy};
 use std::sync::Mutex;
 
 #[derive(Debug)]
 pub struct BotConfiguration {
     automation: Mutex<Automation>,
     delay_config: Mutex<DelayConfig>,
+    leave_rules: Mutex<LeaveRules>,
+    kick_rules: Mutex<KickRules>,
+    reconnect_policy: Mutex<ReconnectPolicy>,
+    reject_early_actions: Mutex<bool>,
+    gems_threshold: Mutex<Option<i32>>,
+    warp_allowlist: Mutex<Vec<String>>,
+    items_dat_url: Mutex<Option<String>>,
+    debug: Mutex<bool>,
+    session_record_path: Mutex<Option<String>>,
+    collect_filter: Mutex<Option<Vec<u16>>>,
+    idle_disconnect: Mutex<Option<u32>>,
+    keepalive_interval: Mutex<Option<u32>>,
+    inventory_full_action: Mutex<LeaveAction>,
+    check_build_access: Mutex<bool>,
+    audit_log: Mutex<bool>,
 }
 
 impl BotConfiguration {
@@ -12,6 +30,21 @@ impl BotConfiguration {
         Self {
             automation: Mutex::new(Automation::default()),
             delay_config: Mutex::new(DelayConfig::default()),
+            leave_rules: Mutex::new(LeaveRules::default()),
+            kick_rules: Mutex::new(KickRules::default()),
+            reconnect_policy: Mutex::new(ReconnectPolicy::default()),
+            reject_early_actions: Mutex::new(false),
+            gems_threshold: Mutex::new(None),
+            warp_allowlist: Mutex::new(Vec::new()),
+            items_dat_url: Mutex::new(None),
+            debug: Mutex::new(false),
+            session_record_path: Mutex::new(None),
+            collect_filter: Mutex::new(None),
+            idle_disconnect: Mutex::new(None),
+            keepalive_interval: Mutex::new(None),
+            inventory_full_action: Mutex::new(LeaveAction::Ignore),
+            check_build_access: Mutex::new(false),
+            audit_log: Mutex::new(false),
         }
     }
 
@@ -26,6 +59,15 @@ impl BotConfiguration {
         auto.auto_collect = enabled;
     }
 
+    pub fn pause_collect_while_moving(&self) -> bool {
+        self.automation.lock().unwrap().pause_collect_while_moving
+    }
+
+    pub fn set_pause_collect_while_moving(&self, enabled: bool) {
+        let mut auto = self.automation.lock().unwrap();
+        auto.pause_collect_while_moving = enabled;
+    }
+
     pub fn auto_reconnect(&self) -> bool {
         self.automation.lock().unwrap().auto_reconnect
     }
@@ -35,6 +77,127 @@ impl BotConfiguration {
         auto.auto_reconnect = enabled;
     }
 
+    pub fn reconnect_policy(&self) -> ReconnectPolicy {
+        self.reconnect_policy.lock().unwrap().clone()
+    }
+
+    pub fn set_reconnect_action(&self, reason: DisconnectReason, action: ReconnectAction) {
+        self.reconnect_policy.lock().unwrap().set(reason, action);
+    }
+
+    /// Whether actions issued before `enter_game` completes are rejected
+    /// instead of queued until the bot is in game.
+    pub fn reject_early_actions(&self) -> bool {
+        *self.reject_early_actions.lock().unwrap()
+    }
+
+    pub fn set_reject_early_actions(&self, reject: bool) {
+        *self.reject_early_actions.lock().unwrap() = reject;
+    }
+
+    /// Milliseconds without actions or a running script before the bot
+    /// disconnects until it is needed again. `None` disables it.
+    pub fn idle_disconnect(&self) -> Option<u32> {
+        *self.idle_disconnect.lock().unwrap()
+    }
+
+    pub fn set_idle_disconnect(&self, timeout_ms: Option<u32>) {
+        *self.idle_disconnect.lock().unwrap() = timeout_ms;
+    }
+
+    /// Milliseconds between keep-alive pings; `None` keeps ENet's default.
+    /// A longer interval rides out slow proxies before the peer times out,
+    /// but a cadence the official client never uses is easier to spot.
+    pub fn keepalive_interval(&self) -> Option<u32> {
+        *self.keepalive_interval.lock().unwrap()
+    }
+
+    pub fn set_keepalive_interval(&self, interval_ms: Option<u32>) {
+        *self.keepalive_interval.lock().unwrap() = interval_ms;
+    }
+
+    pub fn collect_strategy(&self) -> CollectStrategy {
+        self.automation.lock().unwrap().collect_strategy
+    }
+
+    pub fn set_collect_strategy(&self, strategy: CollectStrategy) {
+        let mut auto = self.automation.lock().unwrap();
+        auto.collect_strategy = strategy;
+    }
+
+    pub fn collect_radius(&self) -> Option<u32> {
+        self.automation.lock().unwrap().collect_radius
+    }
+
+    pub fn set_collect_radius(&self, tiles: Option<u32>) {
+        let mut auto = self.automation.lock().unwrap();
+        auto.collect_radius = tiles;
+    }
+
+    /// Item ids `collect` picks up. `None` collects everything.
+    pub fn collect_filter(&self) -> Option<Vec<u16>> {
+        self.collect_filter.lock().unwrap().clone()
+    }
+
+    pub fn set_collect_filter(&self, ids: Option<Vec<u16>>) {
+        *self.collect_filter.lock().unwrap() = ids;
+    }
+
+    pub fn leave_rules(&self) -> LeaveRules {
+        self.leave_rules.lock().unwrap().clone()
+    }
+
+    pub fn set_leave_rules(&self, rules: LeaveRules) {
+        *self.leave_rules.lock().unwrap() = rules;
+    }
+
+    /// What the bot does once auto-collect finds the inventory full, on top
+    /// of pausing collection. `Ignore` only pauses it.
+    pub fn inventory_full_action(&self) -> LeaveAction {
+        self.inventory_full_action.lock().unwrap().clone()
+    }
+
+    pub fn set_inventory_full_action(&self, action: LeaveAction) {
+        *self.inventory_full_action.lock().unwrap() = action;
+    }
+
+    pub fn kick_rules(&self) -> KickRules {
+        self.kick_rules.lock().unwrap().clone()
+    }
+
+    pub fn set_kick_rules(&self, rules: KickRules) {
+        *self.kick_rules.lock().unwrap() = rules;
+    }
+
+    /// Gem total at which `onGemsThreshold` fires. `None` disables it.
+    pub fn gems_threshold(&self) -> Option<i32> {
+        *self.gems_threshold.lock().unwrap()
+    }
+
+    pub fn set_gems_threshold(&self, threshold: Option<i32>) {
+        *self.gems_threshold.lock().unwrap() = threshold;
+    }
+
+    /// Worlds the bot may warp to. An empty list allows every world.
+    pub fn warp_allowlist(&self) -> Vec<String> {
+        self.warp_allowlist.lock().unwrap().clone()
+    }
+
+    pub fn set_warp_allowlist(&self, worlds: Vec<String>) {
+        *self.warp_allowlist.lock().unwrap() = worlds
+            .into_iter()
+            .map(|world| world.to_uppercase())
+            .collect();
+    }
+
+    pub fn is_warp_allowed(&self, world_name: &str) -> bool {
+        let allowlist = self.warp_allowlist.lock().unwrap();
+        allowlist.is_empty()
+            || allowlist
+                .iter()
+                .any(|world| world.eq_ignore_ascii_case(world_name))
+    }
+
     // Delay config getters/setters
 
     pub fn findpath_delay(&self) -> u32 {
@@ -64,6 +227,66 @@ impl BotConfiguration {
         delays.place_delay = delay;
     }
 
+    pub fn world_join_timeout(&self) -> u32 {
+        self.delay_config.lock().unwrap().world_join_timeout
+    }
+
+    pub fn set_world_join_timeout(&self, timeout_ms: u32) {
+        let mut delays = self.delay_config.lock().unwrap();
+        delays.world_join_timeout = timeout_ms;
+    }
+
+    // Item database
+
+    /// Where to download items.dat from when the local copy doesn't match the
+    /// server's hash. `None` requests it from the game server instead.
+    pub fn items_dat_url(&self) -> Option<String> {
+        self.items_dat_url.lock().unwrap().clone()
+    }
+
+    pub fn set_items_dat_url(&self, url: Option<String>) {
+        *self.items_dat_url.lock().unwrap() = url;
+    }
+
+    // Debugging
+
+    /// Enables diagnostics that cost extra work per packet, such as `onRawVariant`
+    /// and keeping the raw inventory payload.
+    pub fn debug(&self) -> bool {
+        *self.debug.lock().unwrap()
+    }
+
+    pub fn set_debug(&self, enabled: bool) {
+        *self.debug.lock().unwrap() = enabled;
+    }
+
+    pub fn session_record_path(&self) -> Option<String> {
+        self.session_record_path.lock().unwrap().clone()
+    }
+
+    pub fn set_session_record_path(&self, path: Option<String>) {
+        *self.session_record_path.lock().unwrap() = path;
+    }
+
+    /// Makes `place` skip tiles the bot can't build on and fire
+    /// `onBuildDenied` instead of sending the request. Off by default.
+    pub fn check_build_access(&self) -> bool {
+        *self.check_build_access.lock().unwrap()
+    }
+
+    pub fn set_check_build_access(&self, enabled: bool) {
+        *self.check_build_access.lock().unwrap() = enabled;
+    }
+
+    /// Records high-level actions in the bot's audit log. Off by default.
+    pub fn audit_log(&self) -> bool {
+        *self.audit_log.lock().unwrap()
+    }
+
+    pub fn set_audit_log(&self, enabled: bool) {
+        *self.audit_log.lock().unwrap() = enabled;
+    }
+
     /// Get all config at once (for API endpoints)
     pub fn get_all(&self) -> (Automation, DelayConfig) {
         let auto = self.automation.lock().unwrap();
@@ -99,6 +322,15 @@ mod tests {
         assert!(config.auto_collect());
     }
 
+    #[test]
+    fn test_pause_collect_while_moving() {
+        let config = BotConfiguration::new();
+        assert!(config.pause_collect_while_moving());
+
+        config.set_pause_collect_while_moving(false);
+        assert!(!config.pause_collect_while_moving());
+    }
+
     #[test]
     fn test_auto_reconnect() {
         let config = BotConfiguration::new();
@@ -108,6 +340,92 @@ mod tests {
         assert!(config.auto_reconnect());
     }
 
+    #[test]
+    fn test_idle_disconnect() {
+        let config = BotConfiguration::new();
+        assert_eq!(config.idle_disconnect(), None);
+
+        config.set_idle_disconnect(Some(60_000));
+        assert_eq!(config.idle_disconnect(), Some(60_000));
+    }
+
+    #[test]
+    fn test_keepalive_interval() {
+        let config = BotConfiguration::new();
+        assert_eq!(config.keepalive_interval(), None);
+
+        config.set_keepalive_interval(Some(2_000));
+        assert_eq!(config.keepalive_interval(), Some(2_000));
+    }
+
+    #[test]
+    fn test_collect_strategy() {
+        let config = BotConfiguration::new();
+        assert_eq!(config.collect_strategy(), CollectStrategy::Greedy);
+
+        config.set_collect_strategy(CollectStrategy::Sweep);
+        assert_eq!(config.collect_strategy(), CollectStrategy::Sweep);
+        assert_eq!(
+            CollectStrategy::from_name("TELEPORT"),
+            Some(CollectStrategy::Teleport)
+        );
+        assert_eq!(CollectStrategy::from_name("nope"), None);
+    }
+
+    #[test]
+    fn test_collect_range() {
+        assert!(CollectStrategy::Greedy.in_range(None, 9216.0));
+        assert!(!CollectStrategy::Greedy.in_range(None, 9217.0));
+        assert!(CollectStrategy::Teleport.in_range(None, 1e9));
+
+        assert!(CollectStrategy::Teleport.in_range(Some(1), 1024.0));
+        assert!(!CollectStrategy::Teleport.in_range(Some(1), 1025.0));
+        assert!(CollectStrategy::Greedy.in_range(Some(u32::MAX), 1e12));
+    }
+
+    #[test]
+    fn test_collect_radius_and_filter() {
+        let config = BotConfiguration::new();
+        assert_eq!(config.collect_radius(), None);
+        assert_eq!(config.collect_filter(), None);
+
+        config.set_collect_radius(Some(5));
+        config.set_collect_filter(Some(vec![3, 5]));
+        assert_eq!(config.collect_radius(), Some(5));
+        assert_eq!(config.collect_filter(), Some(vec![3, 5]));
+    }
+
+    #[test]
+    fn test_leave_rules() {
+        use crate::types::bot::LeaveAction;
+
+        let config = BotConfiguration::new();
+        assert_eq!(config.leave_rules().on_mod, LeaveAction::Leave);
+
+        config.set_leave_rules(LeaveRules {
+            on_mod: LeaveAction::WarpTo("SAFE".to_string()),
+            on_invisible: LeaveAction::Disconnect,
+        });
+        let rules = config.leave_rules();
+        assert_eq!(rules.on_mod, LeaveAction::WarpTo("SAFE".to_string()));
+        assert_eq!(rules.on_invisible, LeaveAction::Disconnect);
+        assert_eq!(LeaveAction::from_name("warp", None), None);
+    }
+
+    #[test]
+    fn test_warp_allowlist() {
+        let config = BotConfiguration::new();
+        assert!(config.is_warp_allowed("ANYWHERE"));
+
+        config.set_warp_allowlist(vec!["home".to_string(), "FARM".to_string()]);
+        assert!(config.is_warp_allowed("HOME"));
+        assert!(config.is_warp_allowed("farm"));
+        assert!(!config.is_warp_allowed("START"));
+
+        config.set_warp_allowlist(Vec::new());
+        assert!(config.is_warp_allowed("START"));
+    }
+
     #[test]
     fn test_delays() {
         let config = BotConfiguration::new();
@@ -120,6 +438,10 @@ mod tests {
 
         config.set_place_delay(300);
         assert_eq!(config.place_delay(), 300);
+
+        assert_eq!(config.world_join_timeout(), 10_000);
+        config.set_world_join_timeout(5_000);
+        assert_eq!(config.world_join_timeout(), 5_000);
     }
 
     #[test]
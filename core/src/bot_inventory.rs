@@ -1,4 +1,4 @@
-use crate::inventory::{Inventory, InventoryItem};
+use crate::inventory::{ITEM_FLAG_WORN, Inventory, InventoryItem};
 use std::sync::{
     Mutex,
     atomic::{AtomicI32, Ordering},
@@ -7,6 +7,7 @@ use std::sync::{
 #[derive(Debug)]
 pub struct BotInventory {
     items: Mutex<Inventory>,
+    raw: Mutex<Vec<u8>>,
     gems: AtomicI32,
 }
 
@@ -14,6 +15,7 @@ impl BotInventory {
     pub fn new() -> Self {
         Self {
             items: Mutex::new(Inventory::new()),
+            raw: Mutex::new(Vec::new()),
             gems: AtomicI32::new(0),
         }
     }
@@ -28,15 +30,37 @@ impl BotInventory {
         self.gems.store(gems, Ordering::SeqCst);
     }
 
+    /// Sets the absolute gem count, clamped at zero, and returns the previous one.
+    pub fn replace_gems(&self, gems: i32) -> i32 {
+        self.gems.swap(gems.max(0), Ordering::SeqCst)
+    }
+
+    /// Applies `delta`, clamping the total at zero, and returns the previous count.
     pub fn add_gems(&self, delta: i32) -> i32 {
-        self.gems.fetch_add(delta, Ordering::SeqCst)
+        self.gems
+            .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |gems| {
+                Some(gems.saturating_add(delta).max(0))
+            })
+            .unwrap_or_else(|gems| gems)
     }
 
     // Inventory operations
 
-    pub fn parse(&self, data: &[u8]) {
+    /// Parses a `SendInventoryState` payload. The raw bytes are only kept when
+    /// `keep_raw` is set, so normal sessions don't copy every packet.
+    pub fn parse(&self, data: &[u8], keep_raw: bool) {
         let mut inv = self.items.lock().unwrap();
         inv.parse(data);
+        if keep_raw {
+            *self.raw.lock().unwrap() = data.to_vec();
+        }
+    }
+
+    /// Raw `SendInventoryState` payload as last received from the server, for
+    /// diagnosing parsing discrepancies. See [`Inventory::parse`] for the layout.
+    /// Empty unless the payload was parsed with `keep_raw`.
+    pub fn raw_bytes(&self) -> Vec<u8> {
+        self.raw.lock().unwrap().clone()
     }
 
     pub fn get_item_count(&self, item_id: u16) -> u8 {
@@ -48,11 +72,52 @@ impl BotInventory {
         self.get_item_count(item_id) >= count
     }
 
+    /// Whether `amount` more of an item would fit, topping up an existing stack
+    /// or taking one free slot. Items occupy a single slot capped at `max_stack`.
+    pub fn has_space_for(&self, item_id: u16, amount: u32, max_stack: u32) -> bool {
+        let inv = self.items.lock().unwrap();
+        match inv.items.get(&item_id) {
+            Some(item) => (item.amount as u32)
+                .checked_add(amount)
+                .is_some_and(|total| total <= max_stack),
+            None => (inv.items.len() as u32) < inv.size && amount <= max_stack,
+        }
+    }
+
+    /// Whether the item is worn, or `None` if it isn't in the inventory.
+    pub fn is_worn(&self, item_id: u16) -> Option<bool> {
+        let inv = self.items.lock().unwrap();
+        inv.items
+            .get(&item_id)
+            .map(|item| item.flag & ITEM_FLAG_WORN != 0)
+    }
+
+    pub fn set_worn(&self, item_id: u16, worn: bool) {
+        let mut inv = self.items.lock().unwrap();
+        if let Some(item) = inv.items.get_mut(&item_id) {
+            if worn {
+                item.flag |= ITEM_FLAG_WORN;
+            } else {
+                item.flag &= !ITEM_FLAG_WORN;
+            }
+        }
+    }
+
     pub fn size_and_count(&self) -> (u32, u16) {
         let inv = self.items.lock().unwrap();
         (inv.size, inv.item_count)
     }
 
+    /// Share of inventory slots in use, from 0.0 to 1.0. An inventory the
+    /// server hasn't sent yet counts as empty.
+    pub fn inventory_fullness(&self) -> f32 {
+        let inv = self.items.lock().unwrap();
+        if inv.size == 0 {
+            return 0.0;
+        }
+        (inv.items.len() as f32 / inv.size as f32).min(1.0)
+    }
+
     pub fn get_all_items(&self) -> Vec<(u16, InventoryItem)> {
         let inv = self.items.lock().unwrap();
         inv.items
@@ -151,6 +216,19 @@ mod tests {
         assert_eq!(inv.gems(), 1300);
     }
 
+    #[test]
+    fn test_worn_state() {
+        let inv = BotInventory::new();
+        assert_eq!(inv.is_worn(48), None);
+
+        inv.add_item(48, 1);
+        assert_eq!(inv.is_worn(48), Some(false));
+        inv.set_worn(48, true);
+        assert_eq!(inv.is_worn(48), Some(true));
+        inv.set_worn(48, false);
+        assert_eq!(inv.is_worn(48), Some(false));
+    }
+
     #[test]
     fn test_item_management() {
         let inv = BotInventory::new();
@@ -189,6 +267,51 @@ mod tests {
         assert_eq!(count, 2);
     }
 
+    #[test]
+    fn test_inventory_fullness() {
+        let inv = BotInventory::new();
+        assert_eq!(inv.inventory_fullness(), 0.0);
+
+        inv.with_inventory_mut(|items| items.size = 4);
+        inv.add_item(1, 10);
+        assert_eq!(inv.inventory_fullness(), 0.25);
+
+        for id in 2..=4 {
+            inv.add_item(id, 1);
+        }
+        assert_eq!(inv.inventory_fullness(), 1.0);
+    }
+
+    #[test]
+    fn test_raw_bytes() {
+        let inv = BotInventory::new();
+        assert!(inv.raw_bytes().is_empty());
+
+        let data = [0u8, 16, 0, 0, 0, 1, 0, 2, 0, 5, 0];
+        inv.parse(&data, false);
+        assert!(inv.raw_bytes().is_empty());
+        assert_eq!(inv.get_item_count(2), 5);
+
+        inv.parse(&data, true);
+        assert_eq!(inv.raw_bytes(), data.to_vec());
+    }
+
+    #[test]
+    fn test_has_space_for() {
+        let inv = BotInventory::new();
+        inv.with_inventory_mut(|inv| inv.size = 2);
+        inv.add_item(10, 150);
+
+        assert!(inv.has_space_for(10, 50, 200));
+        assert!(!inv.has_space_for(10, 51, 200));
+        assert!(!inv.has_space_for(10, u32::MAX, 200));
+        assert!(inv.has_space_for(20, 200, 200));
+        assert!(!inv.has_space_for(20, 201, 200));
+
+        inv.add_item(20, 1);
+        assert!(!inv.has_space_for(30, 1, 200));
+    }
+
     #[test]
     fn test_try_get_snapshot() {
         let inv = BotInventory::new();
@@ -89,6 +89,14 @@ pub enum EventType {
         x: f32,
         y: f32,
     },
+    /// A talk bubble over a player's head.
+    Chat {
+        net_id: i32,
+        message: String,
+    },
+    Console {
+        message: String,
+    },
 
     // Network Events
     Connected {
@@ -98,6 +106,8 @@ pub enum EventType {
     Disconnected {
         reason: Option<String>,
     },
+    /// Back in game after an in-game session dropped.
+    Reconnected,
     PacketReceived {
         packet_type: String,
     },
@@ -120,10 +130,21 @@ pub enum EventType {
     },
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
 pub enum LogLevel {
     Debug,
     Info,
     Warning,
     Error,
 }
+
+impl std::fmt::Display for LogLevel {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            LogLevel::Debug => write!(f, "DEBUG"),
+            LogLevel::Info => write!(f, "INFO"),
+            LogLevel::Warning => write!(f, "WARNING"),
+            LogLevel::Error => write!(f, "ERROR"),
+        }
+    }
+}
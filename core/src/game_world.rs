@@ -1,13 +1,49 @@
+use crate::types::entity::Entity;
 use crate::types::player::Player;
 use gtitem_r::structs::ItemDatabase;
 use std::collections::HashMap;
 use std::sync::{Arc, Mutex, RwLock};
 
+/// Names used when items.dat is missing so the most common items stay readable.
+const FALLBACK_ITEM_NAMES: &[(u32, &str)] = &[
+    (0, "Blank"),
+    (2, "Dirt"),
+    (3, "Dirt Seed"),
+    (4, "Lava"),
+    (6, "Main Door"),
+    (8, "Bedrock"),
+    (10, "Rock"),
+    (12, "Door"),
+    (14, "Cave Background"),
+    (18, "Fist"),
+    (32, "Wrench"),
+    (112, "Gems"),
+    (242, "World Lock"),
+    (1796, "Diamond Lock"),
+    (7188, "Blue Gem Lock"),
+];
+
+/// Foreground id of the white door players spawn at.
+pub const MAIN_DOOR_ID: u16 = 6;
+
+/// Locks that claim the whole world rather than an area around them.
+pub const WORLD_LOCK_IDS: &[u16] = &[242, 1796, 2408, 7188, 10410];
+
+pub fn is_main_lock(tile: &gtworld_r::Tile) -> bool {
+    WORLD_LOCK_IDS.contains(&tile.foreground_item_id)
+        && matches!(tile.tile_type, gtworld_r::TileType::Lock { .. })
+}
+
 #[derive(Debug)]
 pub struct GameWorld {
     pub data: Mutex<gtworld_r::World>,
     pub players: Mutex<HashMap<u32, Player>>,
+    pub entities: Mutex<HashMap<u32, Entity>>,
     pub item_database: Arc<RwLock<ItemDatabase>>,
+    checkpoint: Mutex<Option<(u32, u32)>>,
+    weather: Mutex<u32>,
+    background: Mutex<u32>,
+    unknown_item_format: RwLock<String>,
 }
 
 impl GameWorld {
@@ -15,7 +51,216 @@ impl GameWorld {
         Self {
             data: Mutex::new(gtworld_r::World::new()),
             players: Mutex::new(HashMap::new()),
+            entities: Mutex::new(HashMap::new()),
             item_database,
+            checkpoint: Mutex::new(None),
+            weather: Mutex::new(0),
+            background: Mutex::new(0),
+            unknown_item_format: RwLock::new("Item #{id}".to_string()),
+        }
+    }
+
+    /// Resolves an item name, falling back to the bundled table when the
+    /// database is empty and to the placeholder format otherwise.
+    pub fn item_name(&self, item_id: u32) -> String {
+        {
+            let db = self.item_database.read().unwrap();
+            if let Some(item) = db.get_item(&item_id) {
+                return item.name.clone();
+            }
+
+            if db.items.is_empty() {
+                if let Some((_, name)) = FALLBACK_ITEM_NAMES.iter().find(|(id, _)| *id == item_id) {
+                    return name.to_string();
+                }
+            }
+        }
+
+        format_unknown_item(&self.unknown_item_format.read().unwrap(), item_id)
+    }
+
+    /// Looks up an item id by name, ignoring case. Falls back to the bundled
+    /// names when the database is empty, like [`GameWorld::item_name`].
+    pub fn item_id_by_name(&self, name: &str) -> Option<u32> {
+        let db = self.item_database.read().unwrap();
+        if db.items.is_empty() {
+            return FALLBACK_ITEM_NAMES
+                .iter()
+                .find(|(_, item_name)| item_name.eq_ignore_ascii_case(name))
+                .map(|(id, _)| *id);
+        }
+        db.items
+            .values()
+            .find(|item| item.name.eq_ignore_ascii_case(name))
+            .map(|item| item.id)
+    }
+
+    /// Tile coordinates of the world's main door, if the loaded world has one.
+    pub fn spawn_position(&self) -> Option<(u32, u32)> {
+        let world = self.data.lock().unwrap();
+        world
+            .tiles
+            .iter()
+            .find(|tile| tile.foreground_item_id == MAIN_DOOR_ID)
+            .map(|tile| (tile.x, tile.y))
+    }
+
+    /// Tile coordinates of the lock that owns the world, if it is locked.
+    pub fn main_lock_position(&self) -> Option<(u32, u32)> {
+        let world = self.data.lock().unwrap();
+        world
+            .tiles
+            .iter()
+            .find(|tile| is_main_lock(tile))
+            .map(|tile| (tile.x, tile.y))
+    }
+
+    /// User id of the world owner, from the main lock.
+    pub fn owner_id(&self) -> Option<u32> {
+        let world = self.data.lock().unwrap();
+        world
+            .tiles
+            .iter()
+            .find(|tile| is_main_lock(tile))
+            .and_then(|tile| match &tile.tile_type {
+                gtworld_r::TileType::Lock { owner_uid, .. } => Some(*owner_uid),
+                _ => None,
+            })
+    }
+
+    /// Whether `user_id` may build on the tile at `x, y`: the tile exists and
+    /// the world either has no main lock or the lock lists the user as owner
+    /// or with access. Area locks aren't considered.
+    pub fn can_build_at(&self, user_id: u32, x: u32, y: u32) -> bool {
+        let world = self.data.lock().unwrap();
+        if world.get_tile(x, y).is_none() {
+            return false;
         }
+
+        match world.tiles.iter().find(|tile| is_main_lock(tile)) {
+            Some(lock) => match &lock.tile_type {
+                gtworld_r::TileType::Lock {
+                    owner_uid,
+                    access_uids,
+                    ..
+                } => user_id != 0 && (*owner_uid == user_id || access_uids.contains(&user_id)),
+                _ => false,
+            },
+            None => true,
+        }
+    }
+
+    /// Counts tiles per item id in one pass, by the same rule as
+    /// [`GameWorld::count_tiles`]: a tile counts once for each distinct item
+    /// in its foreground or background, and blank layers are skipped.
+    pub fn tile_histogram(&self) -> HashMap<u16, u32> {
+        let world = self.data.lock().unwrap();
+        let mut counts = HashMap::new();
+        for tile in &world.tiles {
+            let (foreground, background) = (tile.foreground_item_id, tile.background_item_id);
+            if foreground != 0 {
+                *counts.entry(foreground).or_insert(0) += 1;
+            }
+            if background != 0 && background != foreground {
+                *counts.entry(background).or_insert(0) += 1;
+            }
+        }
+        counts
+    }
+
+    /// Number of tiles with `item_id` in the foreground or background. A tile
+    /// holding it in both layers counts once, as in
+    /// [`GameWorld::tile_histogram`].
+    pub fn count_tiles(&self, item_id: u16) -> u32 {
+        let world = self.data.lock().unwrap();
+        world
+            .tiles
+            .iter()
+            .filter(|tile| tile.foreground_item_id == item_id || tile.background_item_id == item_id)
+            .count() as u32
+    }
+
+    /// Tile the server will respawn the bot at, set by touching a checkpoint.
+    /// Cleared whenever a new world loads.
+    pub fn checkpoint(&self) -> Option<(u32, u32)> {
+        *self.checkpoint.lock().unwrap()
+    }
+
+    pub fn set_checkpoint(&self, checkpoint: Option<(u32, u32)>) {
+        *self.checkpoint.lock().unwrap() = checkpoint;
+    }
+
+    /// Weather id set by a weather machine, or 0 for the world's default.
+    /// Cleared whenever a new world loads, like the checkpoint.
+    pub fn weather(&self) -> u32 {
+        *self.weather.lock().unwrap()
+    }
+
+    pub fn set_weather(&self, weather: u32) {
+        *self.weather.lock().unwrap() = weather;
+    }
+
+    /// Background override id the server applied, or 0 when there is none.
+    pub fn background(&self) -> u32 {
+        *self.background.lock().unwrap()
+    }
+
+    pub fn set_background(&self, background: u32) {
+        *self.background.lock().unwrap() = background;
+    }
+
+    /// Where the bot respawns: the checkpoint if one is set, otherwise the main door.
+    pub fn respawn_position(&self) -> Option<(u32, u32)> {
+        self.checkpoint().or_else(|| self.spawn_position())
+    }
+
+    /// Sets the placeholder used for unresolvable items; `{id}` is replaced with the item id.
+    pub fn set_unknown_item_format(&self, format: String) {
+        *self.unknown_item_format.write().unwrap() = format;
+    }
+}
+
+fn format_unknown_item(format: &str, item_id: u32) -> String {
+    if format.contains("{id}") {
+        format.replace("{id}", &item_id.to_string())
+    } else {
+        format!("{} {}", format, item_id)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_format_unknown_item() {
+        assert_eq!(format_unknown_item("Item #{id}", 42), "Item #42");
+        assert_eq!(format_unknown_item("unknown({id})", 7), "unknown(7)");
+        assert_eq!(format_unknown_item("Unknown", 7), "Unknown 7");
+    }
+
+    #[test]
+    fn test_item_id_by_name_falls_back() {
+        let world = GameWorld::new(Arc::new(RwLock::new(ItemDatabase::new())));
+        assert_eq!(world.item_id_by_name("main DOOR"), Some(6));
+        assert_eq!(world.item_id_by_name("Not An Item"), None);
+    }
+
+    #[test]
+    fn test_cannot_build_outside_the_world() {
+        let world = GameWorld::new(Arc::new(RwLock::new(ItemDatabase::new())));
+        assert!(!world.can_build_at(900, 0, 0));
+    }
+
+    #[test]
+    fn test_weather_defaults() {
+        let world = GameWorld::new(Arc::new(RwLock::new(ItemDatabase::new())));
+        assert_eq!(world.weather(), 0);
+        assert_eq!(world.background(), 0);
+
+        world.set_weather(5);
+        world.set_background(14);
+        assert_eq!(world.weather(), 5);
+        assert_eq!(world.background(), 14);
     }
 }
@@ -10,6 +10,9 @@ pub struct Inventory {
     pub items: HashMap<u16, InventoryItem>,
 }
 
+/// `InventoryItem::flag` bit set while the item is worn.
+pub const ITEM_FLAG_WORN: u8 = 1;
+
 #[derive(Debug, Clone)]
 pub struct InventoryItem {
     pub id: u16,
@@ -26,6 +29,8 @@ impl Inventory {
         }
     }
 
+    /// Layout (little endian): `u8` version, `u32` backpack size, `u16` item
+    /// count, then `item count` entries of `u16` id, `u8` amount, `u8` flag.
     pub fn parse(&mut self, data: &[u8]) {
         self.reset();
         let mut data = Cursor::new(data);
@@ -0,0 +1,127 @@
+use std::sync::{Condvar, Mutex};
+use std::time::{Duration, Instant};
+
+/// How long a bot waits on another bot's items.dat fetch before fetching it
+/// itself, in case that bot disconnected halfway.
+pub const FETCH_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Process-wide coordination so bots that see the same new items.dat hash
+/// fetch it once and the rest reload the shared file.
+static SYNC: ItemsDatSync = ItemsDatSync::new();
+
+/// Turns the coordination on or off for every bot. On by default.
+pub fn set_items_dat_sync(enabled: bool) {
+    SYNC.set_enabled(enabled);
+}
+
+pub fn items_dat_sync() -> bool {
+    SYNC.enabled()
+}
+
+pub(crate) fn claim(hash: u32) {
+    SYNC.claim(hash, FETCH_TIMEOUT);
+}
+
+pub(crate) fn release() {
+    SYNC.release();
+}
+
+#[derive(Debug)]
+struct SyncState {
+    enabled: bool,
+    /// Hash some bot is fetching, and since when.
+    fetching: Option<(u32, Instant)>,
+}
+
+#[derive(Debug)]
+pub struct ItemsDatSync {
+    state: Mutex<SyncState>,
+    done: Condvar,
+}
+
+impl ItemsDatSync {
+    pub const fn new() -> Self {
+        Self {
+            state: Mutex::new(SyncState {
+                enabled: true,
+                fetching: None,
+            }),
+            done: Condvar::new(),
+        }
+    }
+
+    pub fn set_enabled(&self, enabled: bool) {
+        self.state.lock().unwrap().enabled = enabled;
+    }
+
+    pub fn enabled(&self) -> bool {
+        self.state.lock().unwrap().enabled
+    }
+
+    /// Waits while another bot fetches `hash`, up to `timeout` from when it
+    /// started, then marks the calling bot as the one fetching. Callers
+    /// should re-check the file afterwards; it may be current by then.
+    pub fn claim(&self, hash: u32, timeout: Duration) {
+        let mut state = self.state.lock().unwrap();
+        if !state.enabled {
+            return;
+        }
+
+        while let Some((pending, started)) = state.fetching {
+            let elapsed = started.elapsed();
+            if pending != hash || elapsed >= timeout {
+                break;
+            }
+            state = self.done.wait_timeout(state, timeout - elapsed).unwrap().0;
+        }
+        state.fetching = Some((hash, Instant::now()));
+    }
+
+    /// Ends the current fetch, successful or not, waking waiting bots.
+    pub fn release(&self) {
+        self.state.lock().unwrap().fetching = None;
+        self.done.notify_all();
+    }
+}
+
+impl Default for ItemsDatSync {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+    use std::thread;
+
+    #[test]
+    fn test_waits_for_the_fetching_bot() {
+        let sync = Arc::new(ItemsDatSync::new());
+        sync.claim(7, Duration::from_secs(5));
+
+        let waiter = {
+            let sync = Arc::clone(&sync);
+            thread::spawn(move || {
+                let started = Instant::now();
+                sync.claim(7, Duration::from_secs(5));
+                started.elapsed()
+            })
+        };
+
+        thread::sleep(Duration::from_millis(50));
+        sync.release();
+        assert!(waiter.join().unwrap() >= Duration::from_millis(50));
+    }
+
+    #[test]
+    fn test_stale_fetch_times_out() {
+        let sync = ItemsDatSync::new();
+        sync.claim(7, Duration::from_millis(20));
+
+        let started = Instant::now();
+        sync.claim(7, Duration::from_millis(20));
+        assert!(started.elapsed() < Duration::from_secs(5));
+    }
+}
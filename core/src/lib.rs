@@ -2,15 +2,23 @@ use crate::bot_configuration::BotConfiguration;
 use crate::bot_inventory::BotInventory;
 use crate::game_world::GameWorld;
 use crate::socks5_udp::Socks5UdpSocket;
-use crate::types::bot::{LoginVia, Scripting, TemporaryData};
+use crate::types::bot::{
+    CollectStrategy, LeaveAction, LoginVia, Route, ScriptRun, Scripting, TemporaryData,
+};
+use crate::types::build::PlaceResult;
+use crate::types::disconnect::{DisconnectReason, ReconnectAction};
+use crate::types::error::MoriError;
+use crate::types::farm::{FarmConfig, FarmCycleStats, FarmReport, FarmStep, FarmStop};
 use crate::types::flags::PacketFlag;
 use crate::types::login_info::LoginInfo;
 use crate::types::net_game_packet::{NetGamePacket, NetGamePacketData};
 use crate::types::net_message::NetMessage;
+use crate::types::player::Friend;
 use crate::types::status::{ENetStatus, PeerStatus};
+use crate::types::warp::WarpResult;
 use gtitem_r::structs::ItemDatabase;
 use rusty_enet::Packet;
-use std::net::{Ipv4Addr, SocketAddr, SocketAddrV4};
+use std::net::{Ipv4Addr, SocketAddr, SocketAddrV4, ToSocketAddrs};
 use std::str::FromStr;
 use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::mpsc;
@@ -25,13 +33,19 @@ mod bot_inventory;
 pub mod events;
 mod game_world;
 mod inventory;
+mod items_sync;
 mod login;
+mod login_throttle;
 mod lua;
 mod movement_controller;
 mod network_session;
 mod packet_handler;
+pub mod proxy;
 mod runtime_context;
+mod script_storage;
+mod send_governor;
 mod server;
+mod session_recorder;
 pub mod socks5_udp;
 pub mod types;
 mod utils;
@@ -41,9 +55,13 @@ pub use authentication_context::AuthenticationContext;
 pub use events::{BotEvent, EventBroadcaster, EventType, LogLevel};
 pub use gtitem_r;
 pub use gtworld_r;
+pub use items_sync::{items_dat_sync, set_items_dat_sync};
+pub use login_throttle::{global_login_rate, set_global_login_rate};
 pub use movement_controller::MovementController;
 pub use network_session::NetworkSession;
-pub use runtime_context::RuntimeContext;
+pub use runtime_context::{ActionSource, AuditEntry, LockContention, LogEntry, RuntimeContext};
+pub use send_governor::{global_send_rate, set_global_send_rate};
+pub use types::error::MoriError;
 
 pub type TokenFetcher = Box<dyn Fn(String, String) -> String + Send + Sync>;
 
@@ -66,10 +84,9 @@ pub struct Bot {
     pub scripting: Scripting,
     pub config: BotConfiguration,
     pub temporary_data: TemporaryData,
-    pub proxy_url: Option<String>,
     pub events: EventBroadcaster,
     pub enet_status: Mutex<ENetStatus>,
-    pub peer_status: Mutex<PeerStatus>,
+    peer_status: Mutex<PeerStatus>,
 }
 
 impl Bot {
@@ -81,20 +98,6 @@ impl Bot {
     ) -> (Arc<Self>, mpsc::Receiver<BotEvent>) {
         let local_addr = SocketAddr::V4(SocketAddrV4::new(Ipv4Addr::UNSPECIFIED, 0));
 
-        let proxy_url = socks5_config.as_ref().map(|cfg| {
-            if let (Some(username), Some(password)) = (&cfg.username, &cfg.password) {
-                format!(
-                    "socks5://{}:{}@{}:{}",
-                    username,
-                    password,
-                    cfg.proxy_addr.ip(),
-                    cfg.proxy_addr.port()
-                )
-            } else {
-                format!("socks5://{}:{}", cfg.proxy_addr.ip(), cfg.proxy_addr.port())
-            }
-        });
-
         let network = NetworkSession::new(local_addr, socks5_config);
         let (event_broadcaster, event_receiver) = events::create_event_channel();
 
@@ -111,7 +114,6 @@ impl Bot {
                 scripting: Scripting::default(),
                 config: BotConfiguration::new(),
                 temporary_data: TemporaryData::default(),
-                proxy_url,
                 events: event_broadcaster,
                 enet_status: Mutex::new(ENetStatus::Disconnected),
                 peer_status: Mutex::new(PeerStatus::FetchingServerData),
@@ -135,17 +137,29 @@ impl Bot {
     }
 
     pub fn connect_to_server(&self) {
-        {
-            let mut peer_status = self.peer_status.lock().unwrap();
-            *peer_status = PeerStatus::FetchingServerData;
+        self.set_peer_status(PeerStatus::FetchingServerData);
+
+        if let Some(Err(e)) = self.network.apply_queued_proxy() {
+            self.runtime
+                .push_log_level(LogLevel::Error, format!("Failed to switch proxy: {}", e));
+        }
+
+        if let Some(address) = self.network.take_queued_endpoint() {
+            self.set_peer_status(PeerStatus::ConnectingToServer);
+            *self.enet_status.lock().unwrap() = ENetStatus::Connecting;
+            self.network.connect(address);
+            return;
         }
 
         if !self.runtime.is_redirecting() {
             {
                 let mut login_info = self.auth.login_info();
                 let info_data = login_info.as_mut().expect("Login info not set");
-                let server_data =
-                    server::get_server_data_with_proxy(false, info_data, self.proxy_url.as_deref());
+                let server_data = server::get_server_data_with_proxy(
+                    false,
+                    info_data,
+                    self.proxy_url().as_deref(),
+                );
                 match server_data {
                     Ok(data) => {
                         info_data.meta = data.meta.clone();
@@ -154,7 +168,7 @@ impl Bot {
                         let dashboard_data = server::get_dashboard_with_proxy(
                             &data.loginurl,
                             info_data,
-                            self.proxy_url.as_deref(),
+                            self.proxy_url().as_deref(),
                         )
                         .expect("Failed to get dashboard data");
                         let mut dashboard = self.auth.dashboard_links();
@@ -168,10 +182,7 @@ impl Bot {
             self.get_token();
         }
 
-        {
-            let mut peer_status = self.peer_status.lock().unwrap();
-            *peer_status = PeerStatus::ConnectingToServer;
-        }
+        self.set_peer_status(PeerStatus::ConnectingToServer);
 
         {
             let mut enet_status = self.enet_status.lock().unwrap();
@@ -194,7 +205,7 @@ impl Bot {
             (login_info.ltoken.clone(), login_info.to_string())
         };
 
-        if let Ok(ltoken) = server::check_token(&ltoken, &login_data, self.proxy_url.as_deref()) {
+        if let Ok(ltoken) = server::check_token(&ltoken, &login_data, self.proxy_url().as_deref()) {
             println!("Refreshed token: {}", ltoken);
             let mut login_info_lock = self.auth.login_info();
             let login_info = login_info_lock.as_mut().expect("Login info not set");
@@ -231,7 +242,7 @@ impl Bot {
                     &growtopia_url.unwrap(),
                     username,
                     password,
-                    self.proxy_url.as_deref(),
+                    self.proxy_url().as_deref(),
                 )
                 .expect("Failed to get legacy token")
             }
@@ -243,14 +254,89 @@ impl Bot {
         login_info.ltoken = token.clone();
     }
 
+    /// Records an action in the audit log when it is enabled. `action` is only
+    /// formatted if the entry will be kept.
+    pub fn audit(&self, source: ActionSource, action: impl FnOnce() -> String) {
+        if self.config.audit_log() {
+            self.runtime.push_audit(source, action());
+        }
+    }
+
+    /// Runs `lua_code` like [`Bot::run_script`], so `stop_script` can abort
+    /// it, printing any error instead of returning it.
     pub fn execute_lua(&self, lua_code: String) {
-        let lua = &self.scripting.lua;
-        if let Err(err) = lua.load(&lua_code).exec() {
-            println!("Failed to execute Lua code: {}", err);
+        match self.run_guarded(&lua_code, "execute script") {
+            None => println!("Failed to execute Lua code: a script is already running"),
+            Some(ScriptRun {
+                error: Some(err), ..
+            }) => println!("Failed to execute Lua code: {}", err),
+            Some(_) => {}
+        }
+    }
+
+    /// Runs `lua_code` to completion on the caller's thread. Returns `None`
+    /// without running anything if another script is still executing.
+    pub fn run_script(&self, lua_code: &str) -> Option<ScriptRun> {
+        self.run_guarded(lua_code, "run script")
+    }
+
+    fn run_guarded(&self, lua_code: &str, action: &str) -> Option<ScriptRun> {
+        let _running = self.scripting.begin_run()?;
+
+        self.audit(ActionSource::Api, || action.to_string());
+        let first_log = self.runtime.logs().len();
+        let error = self
+            .scripting
+            .lua
+            .load(lua_code)
+            .exec()
+            .err()
+            .map(|e| e.to_string());
+        let logs = self
+            .runtime
+            .logs()
+            .get(first_log..)
+            .map(<[LogEntry]>::to_vec)
+            .unwrap_or_default();
+
+        Some(ScriptRun { logs, error })
+    }
+
+    /// Asks the script started by [`Bot::run_script`] to abort with a Lua
+    /// error. `currently_executing` clears as it unwinds, so the next script
+    /// can run. Returns false if no script was running.
+    pub fn stop_script(&self) -> bool {
+        if !self.scripting.currently_executing.load(Ordering::SeqCst) {
+            return false;
         }
+        self.scripting.stop_requested.store(true, Ordering::SeqCst);
+        self.runtime.push_log("Stopping script");
+        true
+    }
+
+    /// Pushes a raw packet through the same handling path as the network thread.
+    #[cfg(feature = "testing")]
+    pub fn feed_packet(self: &Arc<Self>, data: &[u8]) {
+        if data.len() < 4 {
+            return;
+        }
+        packet_handler::handle(self, data);
+    }
+
+    /// Feeds every packet of a session recorded with `set_session_record_path`.
+    #[cfg(feature = "testing")]
+    pub fn replay_session(self: &Arc<Self>, path: &str) -> std::io::Result<usize> {
+        let packets = session_recorder::read_session(path)?;
+        for packet in &packets {
+            self.feed_packet(packet);
+        }
+        Ok(packets.len())
     }
 
     pub fn send_text_packet(&self, msg_type: NetMessage, text: &[u8]) {
+        if self.drop_while_parked() {
+            return;
+        }
         let total_len = 4 + text.len() + 1;
         let mut buffer = Vec::with_capacity(total_len);
         buffer.extend_from_slice(&(msg_type as u32).to_le_bytes());
@@ -272,6 +358,10 @@ impl Bot {
         const GAME_PACKET_DATA_SIZE: usize = 56;
         const MAX_SIZE: usize = 1_000_001;
 
+        if self.drop_while_parked() {
+            return;
+        }
+
         let ext_len = if pkt.flags.contains(PacketFlag::EXTENDED) {
             pkt.extended_data_length as usize
         } else {
@@ -301,12 +391,37 @@ impl Bot {
             Packet::unreliable(buffer)
         };
 
+        send_governor::acquire();
+
+        // Ping replies are sent automatically and don't count as activity.
+        if !matches!(pkt._type, NetGamePacket::PingReply) {
+            self.runtime.mark_action();
+        }
+
         if !self.network.send(enet_packet) {
             self.runtime.push_log("Cannot send packet: No active peer connection.".to_string());
         }
     }
 
+    /// A packet sent while parked after an idle disconnect has no peer to
+    /// go to and can't be replayed. Logs the drop and wakes the bot up so
+    /// the next attempt goes through.
+    fn drop_while_parked(&self) -> bool {
+        if !self.runtime.is_parked() {
+            return false;
+        }
+        self.runtime.request_demand();
+        self.runtime.push_log_level(
+            LogLevel::Warning,
+            "Dropped a packet sent while offline after an idle disconnect, reconnecting",
+        );
+        true
+    }
+
     fn disconnect(&self) {
+        self.runtime
+            .set_disconnect_reason(DisconnectReason::Requested);
+
         // Update enet status based on auto_reconnect setting
         {
             let mut enet_status = self.enet_status.lock().unwrap();
@@ -320,10 +435,185 @@ impl Bot {
         self.network.disconnect();
     }
 
+    /// Sends the quit action so the server drops the session cleanly, then
+    /// disconnects the peer if the server hasn't closed it within a second.
+    pub fn logout(&self) {
+        const LOGOUT_GRACE: Duration = Duration::from_secs(1);
+
+        if self.network.is_connected() {
+            self.send_text_packet(NetMessage::GameMessage, b"action|quit\n");
+
+            let started = Instant::now();
+            while self.network.is_connected() && started.elapsed() < LOGOUT_GRACE {
+                thread::sleep(Duration::from_millis(50));
+            }
+        }
+
+        if self.network.is_connected() {
+            self.disconnect();
+        }
+    }
+
+    /// Recycles the session: drops scripts' listeners and the route, clears
+    /// world/inventory/session state, then logs out so the event loop logs back
+    /// in from scratch. Login credentials and configuration are kept.
+    pub fn restart(&self) {
+        self.stop_route();
+        lua::clear_callbacks(self);
+        lua::cancel_tasks(self);
+
+        *self.world.data.lock().unwrap() = gtworld_r::World::new();
+        self.world.players.lock().unwrap().clear();
+        self.world.entities.lock().unwrap().clear();
+        self.world.set_checkpoint(None);
+        self.world.set_weather(0);
+        self.world.set_background(0);
+        self.inventory.with_inventory_mut(|inv| inv.reset());
+        self.inventory.set_gems(0);
+
+        *self.temporary_data.pending_warp.lock().unwrap() = None;
+        *self.temporary_data.warp_result.lock().unwrap() = None;
+        *self.temporary_data.world_menu.lock().unwrap() = None;
+        *self.temporary_data.friends.lock().unwrap() = None;
+        self.temporary_data.early_actions.lock().unwrap().clear();
+
+        self.runtime.set_redirecting(false);
+        self.runtime.take_throttle();
+        self.runtime.push_log("Restarting bot");
+
+        self.logout();
+    }
+
+    /// `socks5://` URL of the proxy the session currently runs through.
+    pub fn proxy_url(&self) -> Option<String> {
+        self.network.proxy().map(|cfg| cfg.url())
+    }
+
+    /// Checks that the current proxy completes a SOCKS5 UDP association.
+    /// Returns `None` when the bot connects directly.
+    pub fn check_proxy(&self) -> Option<proxy::ProxyCheck> {
+        let cfg = self.network.proxy()?;
+        let check = proxy::check(&cfg);
+        match &check.error {
+            None => self.runtime.push_log(format!(
+                "Proxy {} ok ({}ms)",
+                cfg.proxy_addr,
+                check.latency.as_millis()
+            )),
+            Some(e) => self.runtime.push_log_level(
+                LogLevel::Warning,
+                format!("Proxy {} failed: {}", cfg.proxy_addr, e),
+            ),
+        }
+        self.network.set_last_proxy_check(check.clone());
+        Some(check)
+    }
+
+    pub fn last_proxy_check(&self) -> Option<proxy::ProxyCheck> {
+        self.network.last_proxy_check()
+    }
+
+    /// Switches to `socks5_config` (`None` connects directly). A connected bot
+    /// logs out and picks up the new proxy when the event loop reconnects.
+    pub fn set_proxy(&self, socks5_config: Option<Socks5Config>) {
+        self.network.queue_proxy(socks5_config);
+        self.runtime.set_redirecting(false);
+        self.runtime.push_log("Switching proxy");
+        self.logout();
+    }
+
+    /// Connects straight to `host:port` on the next connect, skipping the
+    /// server data fetch and token request that normally pick the server.
+    /// The login uses whatever token the bot already has, so this suits
+    /// private servers and rejoining a known subserver. Only that one
+    /// connection is affected; reconnects after it route normally. A
+    /// connected bot logs out first.
+    pub fn connect_to(&self, host: &str, port: u16) -> Result<(), MoriError> {
+        let invalid = || MoriError::InvalidAddress(format!("{}:{}", host, port));
+        if port == 0 {
+            return Err(invalid());
+        }
+        let address = (host, port)
+            .to_socket_addrs()
+            .ok()
+            .and_then(|mut addrs| addrs.find(|addr| addr.is_ipv4()))
+            .ok_or_else(invalid)?;
+
+        self.network.queue_endpoint(address);
+        self.runtime.set_redirecting(false);
+        self.runtime
+            .push_log(format!("Connecting directly to {}", address));
+        self.logout();
+        Ok(())
+    }
+
+    /// Honors a "please wait" style message from the server, if `message` is one.
+    pub(crate) fn handle_throttle_message(&self, message: &str) {
+        // Players can type the same words into chat.
+        if utils::console::is_player_chat(message) {
+            return;
+        }
+        if let Some(seconds) = utils::console::parse_throttle(message) {
+            self.runtime
+                .push_log(format!("Server throttled login, waiting {}s", seconds));
+            self.runtime.set_throttle(seconds);
+            lua::invoke_callbacks(self, "onThrottled", seconds);
+        }
+    }
+
+    /// Fires `onKicked` when a system console message matches a kick pattern, and
+    /// applies the configured reconnect cooldown.
+    pub(crate) fn handle_kick_message(&self, message: &str) {
+        // Only the server can kick; the same words in chat are just chat.
+        if utils::console::is_player_chat(message) {
+            return;
+        }
+        let rules = self.config.kick_rules();
+        if utils::console::find_pattern(message, &rules.patterns).is_none() {
+            return;
+        }
+
+        let reason = utils::console::strip_color_codes(message);
+        self.runtime.push_log(format!("Kicked: {}", reason));
+        self.runtime.set_disconnect_reason(DisconnectReason::Kicked);
+        if rules.cooldown_secs > 0 {
+            self.runtime.set_throttle(rules.cooldown_secs);
+        }
+        lua::invoke_callbacks(self, "onKicked", reason);
+    }
+
+    /// Records an account ban or suspension so the reconnect policy can act
+    /// on it. Only the logon-fail text is classified; world bans and chat
+    /// mentioning bans don't count.
+    pub(crate) fn handle_ban_message(&self, message: &str) {
+        let plain = utils::console::strip_color_codes(message);
+        if let Some(reason) = DisconnectReason::from_server_message(&plain) {
+            self.runtime
+                .push_log(format!("Account {}: {}", reason, plain));
+            self.runtime.set_disconnect_reason(reason);
+        }
+    }
+
+    pub fn set_reconnect_action(&self, reason: DisconnectReason, action: ReconnectAction) {
+        self.config.set_reconnect_action(reason, action);
+    }
+
+    pub fn set_leave_rules(&self, rules: types::bot::LeaveRules) {
+        self.config.set_leave_rules(rules);
+    }
+
+    pub fn set_warp_allowlist(&self, worlds: Vec<String>) {
+        self.config.set_warp_allowlist(worlds);
+    }
+
     pub fn set_auto_collect(&self, enabled: bool) {
         self.config.set_auto_collect(enabled);
     }
 
+    pub fn set_pause_collect_while_moving(&self, enabled: bool) {
+        self.config.set_pause_collect_while_moving(enabled);
+    }
+
     pub fn set_auto_reconnect(&self, enabled: bool) {
         self.config.set_auto_reconnect(enabled);
     }
@@ -340,12 +630,124 @@ impl Bot {
         self.config.set_place_delay(delay);
     }
 
+    pub fn set_world_join_timeout(&self, timeout_ms: u32) {
+        self.config.set_world_join_timeout(timeout_ms);
+    }
+
+    pub fn set_items_dat_url(&self, url: Option<String>) {
+        self.config.set_items_dat_url(url);
+    }
+
+    pub fn set_debug(&self, enabled: bool) {
+        self.config.set_debug(enabled);
+    }
+
+    pub fn set_reject_early_actions(&self, reject: bool) {
+        self.config.set_reject_early_actions(reject);
+    }
+
+    pub fn set_idle_disconnect(&self, timeout_ms: Option<u32>) {
+        self.config.set_idle_disconnect(timeout_ms);
+    }
+
+    /// Applies to the current connection right away and to every later one.
+    pub fn set_keepalive_interval(&self, interval_ms: Option<u32>) {
+        self.config.set_keepalive_interval(interval_ms);
+        self.apply_keepalive_interval();
+    }
+
+    fn apply_keepalive_interval(&self) {
+        let interval = self
+            .config
+            .keepalive_interval()
+            .map(|ms| Duration::from_millis(ms as u64))
+            .unwrap_or(network_session::DEFAULT_PING_INTERVAL);
+        self.network.set_ping_interval(interval);
+    }
+
+    /// Whether `enter_game` has completed, so the server accepts actions.
+    pub fn is_ready(&self) -> bool {
+        matches!(self.peer_status(), PeerStatus::InGame | PeerStatus::InWorld)
+    }
+
+    /// Holds `action` until the bot is in game, or rejects it when
+    /// `reject_early_actions` is set or the queue is full. The action replays
+    /// as if called then, so callers that need an outcome now, or act relative
+    /// to the current position, should check [`Bot::is_ready`] first.
+    fn defer_action(&self, name: &str, action: impl FnOnce(&Bot) + Send + 'static) {
+        const MAX_EARLY_ACTIONS: usize = 16;
+
+        // Even a rejected action means the bot is wanted again.
+        self.runtime.request_demand();
+
+        let rejected = if self.config.reject_early_actions() {
+            Some("the bot hasn't entered the game yet")
+        } else {
+            let mut queue = self.temporary_data.early_actions.lock().unwrap();
+            if queue.len() < MAX_EARLY_ACTIONS {
+                queue.push_back(Box::new(action));
+                None
+            } else {
+                Some("too many actions queued before entering the game")
+            }
+        };
+
+        match rejected {
+            Some(reason) => {
+                self.runtime
+                    .push_log_level(LogLevel::Error, format!("Rejected {}: {}", name, reason));
+                lua::invoke_callbacks(self, "onActionRejected", (name.to_string(), reason));
+            }
+            None => self
+                .runtime
+                .push_log(format!("Queued {} until the bot enters the game", name)),
+        }
+    }
+
+    /// Marks the bot as in game and replays actions queued before that, off the
+    /// network thread since they may sleep.
+    pub(crate) fn set_in_game(self: &Arc<Self>) {
+        self.set_peer_status(PeerStatus::InGame);
+
+        if self.runtime.take_session_lost() {
+            self.events.emit(BotEvent::new(EventType::Reconnected));
+            lua::invoke_callbacks(self, "onReconnect", ());
+        }
+
+        let queued: Vec<_> = self
+            .temporary_data
+            .early_actions
+            .lock()
+            .unwrap()
+            .drain(..)
+            .collect();
+        if queued.is_empty() {
+            return;
+        }
+        let bot = Arc::clone(self);
+        thread::spawn(move || {
+            for action in queued {
+                action(&bot);
+            }
+        });
+    }
+
+    /// Records every received packet to `path` (or stops recording with `None`).
+    pub fn set_session_record_path(&self, path: Option<String>) {
+        self.config.set_session_record_path(path);
+    }
+
     pub fn enet_status(&self) -> ENetStatus {
         *self.enet_status.lock().unwrap()
     }
 
+    /// Status reads and writes survive a panic elsewhere that poisoned the lock.
     pub fn peer_status(&self) -> PeerStatus {
-        *self.peer_status.lock().unwrap()
+        *utils::sync::lock_unpoisoned(&self.peer_status)
+    }
+
+    pub fn set_peer_status(&self, status: PeerStatus) {
+        *utils::sync::lock_unpoisoned(&self.peer_status) = status;
     }
 
     pub fn sleep_with_timeout(&self, seconds: u64) {
@@ -385,8 +787,13 @@ impl Bot {
                     bot_arc.runtime.set_ping(ping);
                 }
 
-                if bot_arc.config.auto_collect() {
-                    bot_arc.collect();
+                bot_arc.check_warp_timeout();
+                bot_arc.check_idle_disconnect();
+
+                let paused =
+                    bot_arc.config.pause_collect_while_moving() && bot_arc.movement.is_moving();
+                if bot_arc.config.auto_collect() && !paused && !bot_arc.check_inventory_full() {
+                    bot_arc.collect(ActionSource::Auto);
                 }
 
                 thread::sleep(COLLECT_INTERVAL);
@@ -395,14 +802,27 @@ impl Bot {
     }
 
     fn process_event(self: Arc<Self>) {
+        send_governor::exempt_current_thread();
+        let mut backoff_streak = 0;
         loop {
             let is_running = self.runtime.is_running();
 
             if !is_running {
                 break;
             }
+
+            let throttle = self.runtime.take_throttle();
+            if throttle > 0 {
+                self.sleep_with_timeout(throttle);
+            }
+
+            // Redirects to the game server are part of the same login.
+            if !self.runtime.is_redirecting() {
+                login_throttle::acquire();
+            }
             self.connect_to_server();
 
+            let mut connected = false;
             loop {
                 let event = self.network.service();
 
@@ -411,6 +831,9 @@ impl Bot {
                         rusty_enet::EventNoRef::Connect { peer, .. } => {
                             println!("Connected to server");
                             self.network.set_peer_id(Some(peer));
+                            self.apply_keepalive_interval();
+                            self.runtime.clear_disconnect_reason();
+                            connected = true;
 
                             // Update enet status to Connected
                             {
@@ -441,21 +864,58 @@ impl Bot {
                             if data.len() < 4 {
                                 continue;
                             }
+                            if let Some(path) = self.config.session_record_path() {
+                                if let Err(e) = session_recorder::append(&path, data) {
+                                    self.runtime
+                                        .push_log(format!("Failed to record packet: {}", e));
+                                }
+                            }
                             packet_handler::handle(&self, data);
                         }
                         rusty_enet::EventNoRef::Disconnect { peer: _, data: _ } => {
                             println!("Disconnected from server");
                             self.network.set_peer_id(None);
 
+                            // Hopping to the game server isn't a lost session.
+                            if self.is_ready() && !self.runtime.is_redirecting() {
+                                self.runtime.mark_session_lost();
+                            }
+                            if self.temporary_data.pending_warp.lock().unwrap().is_some() {
+                                self.finish_warp(WarpResult::Failed("disconnected".to_string()));
+                            }
+
+                            // A session that made it in game ends the backoff streak.
+                            if self.is_ready() {
+                                backoff_streak = 0;
+                            }
+                            // Nothing can be sent until the next login, so
+                            // actions are queued instead of going to a dead peer.
+                            self.set_peer_status(PeerStatus::FetchingServerData);
+
                             {
                                 let mut enet_status = self.enet_status.lock().unwrap();
                                 *enet_status = ENetStatus::Disconnected;
                             }
 
-                            self.events
-                                .emit(BotEvent::new(EventType::Disconnected { reason: None }));
-
-                            lua::invoke_callbacks(&self, "onDisconnect", ());
+                            let reason = self.runtime.take_disconnect_reason().unwrap_or(
+                                if !connected && self.network.proxy().is_some() {
+                                    DisconnectReason::ProxyError
+                                } else {
+                                    DisconnectReason::Timeout
+                                },
+                            );
+                            self.events.emit(BotEvent::new(EventType::Disconnected {
+                                reason: Some(reason.to_string()),
+                            }));
+
+                            lua::invoke_callbacks(&self, "onDisconnect", reason.to_string());
+                            lua::cancel_tasks(&self);
+
+                            if reason == DisconnectReason::Idle {
+                                self.wait_for_demand();
+                            } else if !self.wait_before_reconnect(reason, &mut backoff_streak) {
+                                return;
+                            }
                             break;
                         }
                     }
@@ -463,24 +923,305 @@ impl Bot {
             }
         }
     }
+
+    /// Whether auto-collect should pause because every slot is taken. The
+    /// first check that finds it full fires `onInventoryFull` and runs the
+    /// configured action; collection resumes once a slot frees up.
+    fn check_inventory_full(&self) -> bool {
+        let full = self.inventory.inventory_fullness() >= 1.0;
+        let was_full = self
+            .temporary_data
+            .inventory_full
+            .swap(full, Ordering::SeqCst);
+        if full && !was_full {
+            self.runtime
+                .push_log("Inventory full, pausing auto-collect");
+            lua::invoke_callbacks(self, "onInventoryFull", ());
+            self.apply_leave_action(&self.config.inventory_full_action());
+        }
+        full
+    }
+
+    /// Disconnects once the bot has been idle for the configured time. Actions
+    /// requested afterwards are queued and bring it back online.
+    fn check_idle_disconnect(&self) {
+        let Some(timeout_ms) = self.config.idle_disconnect() else {
+            return;
+        };
+        let idle = self.runtime.idle_time();
+        if !self.is_ready()
+            || self.scripting.currently_executing.load(Ordering::SeqCst)
+            || idle < Duration::from_millis(timeout_ms as u64)
+        {
+            return;
+        }
+
+        self.runtime
+            .push_log(format!("Idle for {}s, disconnecting", idle.as_secs()));
+        lua::invoke_callbacks(self, "onIdleDisconnect", idle.as_millis() as u64);
+        self.runtime.set_disconnect_reason(DisconnectReason::Idle);
+        self.set_peer_status(PeerStatus::FetchingServerData);
+        self.disconnect();
+    }
+
+    /// Blocks after an idle disconnect until an action is attempted or the
+    /// bot stops.
+    fn wait_for_demand(&self) {
+        self.runtime.take_demand();
+        self.runtime.set_parked(true);
+        while self.runtime.is_running()
+            && !self.runtime.take_demand()
+            && self.temporary_data.early_actions.lock().unwrap().is_empty()
+        {
+            thread::sleep(Duration::from_millis(100));
+        }
+        self.runtime.set_parked(false);
+        self.runtime.mark_action();
+    }
+
+    /// Applies the reconnect policy for `reason`. Returns false when the bot
+    /// should stay offline, in which case it stops running.
+    fn wait_before_reconnect(&self, reason: DisconnectReason, backoff_streak: &mut u32) -> bool {
+        let action = self.config.reconnect_policy().action(reason);
+        match action {
+            ReconnectAction::Immediate => {
+                *backoff_streak = 0;
+                true
+            }
+            ReconnectAction::Backoff(_) => {
+                let delay = action.delay_secs(*backoff_streak);
+                *backoff_streak += 1;
+                self.runtime.push_log(format!(
+                    "Disconnected ({}), reconnecting in {}s",
+                    reason, delay
+                ));
+                self.sleep_with_timeout(delay);
+                true
+            }
+            ReconnectAction::Never => {
+                self.runtime
+                    .push_log(format!("Disconnected ({}), not reconnecting", reason));
+                *self.enet_status.lock().unwrap() = ENetStatus::Disconnected;
+                self.runtime.set_running(false);
+                false
+            }
+        }
+    }
 }
 
 // packet methods
 impl Bot {
-    pub fn say(&self, message: &str) {
+    pub fn say(&self, source: ActionSource, message: &str) {
+        self.audit(source, || format!("say {}", message));
         self.send_text_packet(
             NetMessage::GenericText,
             format!("action|input\n|text|{}\n", message).as_bytes(),
         );
     }
 
-    pub fn warp(&self, world_name: String) {
+    pub fn warp(&self, source: ActionSource, world_name: String) {
+        self.warp_at(source, world_name, None);
+    }
+
+    /// Warps and asks the server to place the bot at `enter` instead of the
+    /// main door. See [`types::warp::join_request`] for server support.
+    pub fn warp_at(&self, source: ActionSource, world_name: String, enter: Option<(u32, u32)>) {
+        self.audit(source, || match enter {
+            Some((x, y)) => format!("warp {} at {},{}", world_name, x, y),
+            None => format!("warp {}", world_name),
+        });
+        self.join_world(world_name, enter);
+    }
+
+    fn join_world(&self, world_name: String, enter: Option<(u32, u32)>) {
+        if !self.is_ready() {
+            self.defer_action("warp", move |bot| bot.join_world(world_name, enter));
+            return;
+        }
+
+        if !self.config.is_warp_allowed(&world_name) {
+            self.runtime
+                .push_log(format!("Warp to {} blocked by the allowlist", world_name));
+            *self.temporary_data.warp_result.lock().unwrap() =
+                Some(WarpResult::Failed("not in warp allowlist".to_string()));
+            lua::invoke_callbacks(self, "onWarpBlocked", world_name);
+            return;
+        }
+
+        *self.temporary_data.pending_warp.lock().unwrap() =
+            Some((world_name.clone(), Instant::now()));
         self.send_text_packet(
             NetMessage::GameMessage,
-            format!("action|join_request\nname|{}\ninvitedWorld|0\n", world_name).as_bytes(),
+            types::warp::join_request(&world_name, enter).as_bytes(),
         );
     }
 
+    /// Resolves the pending warp, if any, and tells scripts how it went:
+    /// `onWorldEnter` with the world the server loaded, or `onWarpFailed`.
+    pub(crate) fn finish_warp(&self, result: WarpResult) {
+        let pending = self.temporary_data.pending_warp.lock().unwrap().take();
+        *self.temporary_data.warp_result.lock().unwrap() = Some(result.clone());
+
+        let Some((world_name, _)) = pending else {
+            return;
+        };
+        match &result {
+            WarpResult::Entered(entered) => {
+                lua::invoke_callbacks(self, "onWorldEnter", entered.clone());
+            }
+            _ => lua::invoke_callbacks(self, "onWarpFailed", (world_name, result.to_string())),
+        }
+    }
+
+    /// Aborts a join the server never answered within the configured world-join timeout.
+    fn check_warp_timeout(&self) {
+        let timeout = Duration::from_millis(self.config.world_join_timeout() as u64);
+        let expired = {
+            let pending = self.temporary_data.pending_warp.lock().unwrap();
+            matches!(&*pending, Some((_, started)) if started.elapsed() >= timeout)
+        };
+
+        if !expired {
+            return;
+        }
+
+        // Back to the menu, but only if the session is still up; a peer that
+        // dropped meanwhile keeps the status the reconnect gave it.
+        if self.network.is_connected() {
+            let mut peer_status = utils::sync::lock_unpoisoned(&self.peer_status);
+            if matches!(*peer_status, PeerStatus::InGame | PeerStatus::InWorld) {
+                *peer_status = PeerStatus::InGame;
+            }
+        }
+
+        let pending = self.temporary_data.pending_warp.lock().unwrap().take();
+        *self.temporary_data.warp_result.lock().unwrap() = Some(WarpResult::Timeout);
+        if let Some((world_name, _)) = pending {
+            self.runtime
+                .push_log(format!("Timed out joining world {}", world_name));
+            lua::invoke_callbacks(self, "onWarpFailed", (world_name, "timeout".to_string()));
+        }
+    }
+
+    /// Sends a join request and blocks until the server either loads the world,
+    /// rejects the join, or `timeout` elapses. Fails at once, without queuing
+    /// the warp, if the bot isn't in game.
+    pub fn warp_and_wait(
+        &self,
+        source: ActionSource,
+        world_name: String,
+        timeout: Duration,
+    ) -> WarpResult {
+        if !self.is_ready() {
+            self.runtime.request_demand();
+            return WarpResult::Failed("not in game".to_string());
+        }
+        *self.temporary_data.warp_result.lock().unwrap() = None;
+        self.warp(source, world_name);
+
+        let started = Instant::now();
+        while started.elapsed() < timeout {
+            if let Some(result) = self.temporary_data.warp_result.lock().unwrap().take() {
+                return result;
+            }
+            thread::sleep(Duration::from_millis(100));
+        }
+
+        WarpResult::Timeout
+    }
+
+    pub fn set_route(&self, worlds: Vec<String>, dwell_ms: u64) {
+        *self.temporary_data.route.lock().unwrap() = Some(Route { worlds, dwell_ms });
+    }
+
+    /// Starts cycling through the configured route on a background thread.
+    /// Returns `false` if no route is set or one is already running.
+    pub fn start_route(self: &Arc<Self>) -> bool {
+        let has_route = self
+            .temporary_data
+            .route
+            .lock()
+            .unwrap()
+            .as_ref()
+            .is_some_and(|route| !route.worlds.is_empty());
+        if !has_route
+            || self
+                .temporary_data
+                .route_running
+                .swap(true, Ordering::SeqCst)
+        {
+            return false;
+        }
+
+        let bot_arc = Arc::clone(self);
+        thread::spawn(move || {
+            const STEP: Duration = Duration::from_millis(100);
+            // Pause after a failed hop, so a world that fails straight away
+            // doesn't turn into a stream of join requests.
+            const RETRY_DELAY: Duration = Duration::from_secs(2);
+            const MAX_FAILED_HOPS: u32 = 5;
+
+            let wait = |duration: Duration| {
+                let started = Instant::now();
+                while bot_arc.is_route_active() && started.elapsed() < duration {
+                    thread::sleep(STEP);
+                }
+            };
+            let mut index = 0;
+            let mut failed_hops = 0;
+
+            while bot_arc.is_route_active() {
+                let Some(route) = bot_arc.temporary_data.route.lock().unwrap().clone() else {
+                    break;
+                };
+                if route.worlds.is_empty() {
+                    break;
+                }
+
+                let world_name = route.worlds[index % route.worlds.len()].clone();
+                index += 1;
+
+                let timeout = Duration::from_millis(bot_arc.config.world_join_timeout() as u64);
+                let result = bot_arc.warp_and_wait(ActionSource::Auto, world_name.clone(), timeout);
+                if !result.is_entered() {
+                    bot_arc
+                        .runtime
+                        .push_log(format!("Route: skipping {} ({})", world_name, result));
+                    failed_hops += 1;
+                    if failed_hops >= MAX_FAILED_HOPS {
+                        bot_arc.runtime.push_log(format!(
+                            "Route: stopping after {} failed hops in a row",
+                            failed_hops
+                        ));
+                        break;
+                    }
+                    wait(RETRY_DELAY);
+                    continue;
+                }
+
+                failed_hops = 0;
+                wait(Duration::from_millis(route.dwell_ms));
+            }
+
+            bot_arc
+                .temporary_data
+                .route_running
+                .store(false, Ordering::SeqCst);
+        });
+
+        true
+    }
+
+    pub fn stop_route(&self) {
+        self.temporary_data
+            .route_running
+            .store(false, Ordering::SeqCst);
+    }
+
+    fn is_route_active(&self) -> bool {
+        self.runtime.is_running() && self.temporary_data.route_running.load(Ordering::SeqCst)
+    }
+
     pub fn leave(&self) {
         if self.peer_status() == PeerStatus::InWorld {
             self.send_text_packet(
@@ -490,9 +1231,66 @@ impl Bot {
         }
     }
 
-    pub fn place(&self, offset_x: i32, offset_y: i32, item_id: u32, is_punch: bool) {
+    pub fn apply_leave_action(&self, action: &LeaveAction) {
+        if *action != LeaveAction::Ignore {
+            self.audit(ActionSource::Auto, || format!("leave rule: {:?}", action));
+        }
+        match action {
+            LeaveAction::Leave => self.leave(),
+            LeaveAction::WarpTo(world_name) => self.warp(ActionSource::Auto, world_name.clone()),
+            LeaveAction::Disconnect => self.disconnect(),
+            LeaveAction::Ignore => {}
+        }
+    }
+
+    /// Growtopia has no client packet for setting a custom on-screen status or
+    /// title; titles are granted server-side. This logs a warning and returns
+    /// `false` so scripts can tell nothing was sent.
+    pub fn set_status_text(&self, text: &str) -> bool {
+        self.runtime.push_log_level(
+            LogLevel::Warning,
+            format!(
+                "set_status_text(\"{}\") is not supported by this server, ignoring",
+                text
+            ),
+        );
+        false
+    }
+
+    /// The client has no request for a fresh `SendInventoryState`; the server
+    /// only pushes it on login and world entry. Logs a warning and returns
+    /// `false`; rejoining the world is the reliable way to resync.
+    pub fn refresh_inventory(&self) -> bool {
+        self.runtime.push_log_level(
+            LogLevel::Warning,
+            "Inventory refresh is not supported by this server, rejoin the world to resync",
+        );
+        false
+    }
+
+    /// Places `item_id` at the tile offset from the bot. Returns whether the
+    /// packets went out. Before the bot is in game the place is queued instead
+    /// and returns false; it replays relative to wherever the bot stands then.
+    pub fn place(&self, source: ActionSource, offset_x: i32, offset_y: i32, item_id: u32) -> bool {
+        self.audit(source, || {
+            format!("place {} at {},{}", item_id, offset_x, offset_y)
+        });
+        self.tile_action(offset_x, offset_y, item_id, false)
+    }
+
+    /// Sends a place (or punch) at the tile offset from the bot, queuing it
+    /// like [`Bot::place`] before the bot is in game.
+    fn tile_action(&self, offset_x: i32, offset_y: i32, item_id: u32, is_punch: bool) -> bool {
+        if !self.is_ready() {
+            let name = if is_punch { "punch" } else { "place" };
+            self.defer_action(name, move |bot| {
+                bot.tile_action(offset_x, offset_y, item_id, is_punch);
+            });
+            return false;
+        }
+
         if !is_punch && !self.inventory.has_item(item_id as u16, 1) {
-            return;
+            return false;
         }
 
         let mut pkt = NetGamePacketData::default();
@@ -502,6 +1300,13 @@ impl Bot {
         pkt.vector_y = position.1;
         let base_x = (position.0 / 32.0).floor() as i32;
         let base_y = (position.1 / 32.0).floor() as i32;
+
+        let (x, y) = (base_x + offset_x, base_y + offset_y);
+        if !is_punch && self.config.check_build_access() && !self.can_build_at(x, y) {
+            lua::invoke_callbacks(self, "onBuildDenied", (x, y, item_id));
+            return false;
+        }
+
         pkt.int_x = base_x + offset_x;
         pkt.int_y = base_y + offset_y;
         pkt.value = item_id;
@@ -523,25 +1328,210 @@ impl Bot {
             pkt._type = NetGamePacket::State;
             self.send_game_packet(&pkt, None, true);
             thread::sleep(Duration::from_millis(250));
+            return true;
         }
+        false
     }
 
-    pub fn punch(&self, offset_x: i32, offset_y: i32) {
-        self.place(offset_x, offset_y, 18, true);
+    /// Places `(x, y, item_id)` tiles in world coordinates, pathing once per
+    /// cluster of tiles within reach instead of once per tile. Returns one
+    /// result per placement, in input order. Tiles planned around an
+    /// unreachable spot are reported as unreachable rather than re-planned,
+    /// and the tile the bot stands on is skipped since nothing can be placed
+    /// inside the bot. Nothing is queued: placements left when the bot isn't
+    /// in game are reported as not ready.
+    pub fn batch_place(
+        &self,
+        source: ActionSource,
+        placements: Vec<(i32, i32, u32)>,
+    ) -> Vec<PlaceResult> {
+        self.audit(source, || format!("batch place {} tiles", placements.len()));
+        let mut results = vec![PlaceResult::Stopped; placements.len()];
+        let position = self.movement.position();
+        let start = ((position.0 / 32.0) as i32, (position.1 / 32.0) as i32);
+
+        for cluster in types::build::plan_clusters(&placements, start) {
+            if self.scripting.stop_requested.load(Ordering::SeqCst) {
+                break;
+            }
+            // Pathing and placing would only queue up behind the login.
+            if !self.is_ready() {
+                self.runtime.request_demand();
+                for result in results.iter_mut().filter(|r| **r == PlaceResult::Stopped) {
+                    *result = PlaceResult::NotReady;
+                }
+                break;
+            }
+
+            let (stand_x, stand_y) = cluster.stand;
+            if stand_x < 0
+                || stand_y < 0
+                || self.path_length(stand_x as u32, stand_y as u32).is_none()
+            {
+                for i in cluster.placements {
+                    results[i] = PlaceResult::Unreachable;
+                }
+                continue;
+            }
+            self.find_path(stand_x as u32, stand_y as u32);
+
+            for i in cluster.placements {
+                let (x, y, item_id) = placements[i];
+                if !self.is_ready() {
+                    results[i] = PlaceResult::NotReady;
+                    continue;
+                }
+                if !self.inventory.has_item(item_id as u16, 1) {
+                    results[i] = PlaceResult::MissingItem;
+                    continue;
+                }
+                let on_stand = (x, y) == cluster.stand;
+                if on_stand || !self.tile_action(x - stand_x, y - stand_y, item_id, false) {
+                    results[i] = PlaceResult::Skipped;
+                    continue;
+                }
+                results[i] = PlaceResult::Placed;
+                thread::sleep(Duration::from_millis(self.config.place_delay() as u64));
+            }
+        }
+
+        results
     }
 
-    pub fn wrench(&self, offset_x: i32, offset_y: i32) {
-        self.place(offset_x, offset_y, 32, false);
+    pub fn punch(&self, source: ActionSource, offset_x: i32, offset_y: i32) {
+        self.audit(source, || format!("punch at {},{}", offset_x, offset_y));
+        self.tile_action(offset_x, offset_y, 18, true);
     }
 
-    pub fn wrench_player(&self, net_id: u32) {
+    /// Punches a spawned player. Returns `false` if they aren't in the world or
+    /// are out of punch range (4 tiles), like tile punches.
+    pub fn punch_player(&self, source: ActionSource, net_id: u32) -> bool {
+        self.audit(source, || format!("punch player {}", net_id));
+        let target = {
+            let players = self.world.players.lock().unwrap();
+            match players.get(&net_id) {
+                Some(player) => player.position,
+                None => return false,
+            }
+        };
+
+        let position = self.movement.position();
+        let base_x = (position.0 / 32.0).floor() as i32;
+        let base_y = (position.1 / 32.0).floor() as i32;
+        let target_x = (target.0 / 32.0).floor() as i32;
+        let target_y = (target.1 / 32.0).floor() as i32;
+        if (target_x - base_x).abs() > 4 || (target_y - base_y).abs() > 4 {
+            return false;
+        }
+
+        let mut pkt = NetGamePacketData::default();
+        pkt._type = NetGamePacket::State;
+        pkt.vector_x = position.0;
+        pkt.vector_y = position.1;
+        pkt.int_x = target_x;
+        pkt.int_y = target_y;
+        pkt.value = 18;
+        pkt.flags = PacketFlag::PUNCH | PacketFlag::STANDING;
+        if base_x > target_x {
+            pkt.flags |= PacketFlag::FACING_LEFT;
+        }
+        self.send_game_packet(&pkt, None, true);
+
+        thread::sleep(Duration::from_millis(self.config.punch_delay() as u64));
+        true
+    }
+
+    pub fn wrench(&self, source: ActionSource, offset_x: i32, offset_y: i32) {
+        self.audit(source, || format!("wrench at {},{}", offset_x, offset_y));
+        self.tile_action(offset_x, offset_y, 32, false);
+    }
+
+    pub fn wrench_player(&self, source: ActionSource, net_id: u32) {
+        self.audit(source, || format!("wrench player {}", net_id));
+        self.send_wrench_player(net_id);
+    }
+
+    fn send_wrench_player(&self, net_id: u32) {
         self.send_text_packet(
             NetMessage::GenericText,
             format!("action|wrench\n|netid|{}\n", net_id).as_bytes(),
         );
     }
 
+    /// Whether the bot may build on the tile at `x, y`, judged from the
+    /// world's main lock. See [`GameWorld::can_build_at`].
+    pub fn can_build_at(&self, x: i32, y: i32) -> bool {
+        if x < 0 || y < 0 {
+            return false;
+        }
+        self.world
+            .can_build_at(self.runtime.user_id(), x as u32, y as u32)
+    }
+
+    /// Whether the main lock of the current world belongs to the bot.
+    pub fn owns_world(&self) -> bool {
+        let user_id = self.runtime.user_id();
+        user_id != 0 && self.world.owner_id() == Some(user_id)
+    }
+
+    /// Kicks a player out of the world. See [`Bot::manage_player`].
+    pub fn kick_player(&self, net_id: u32) -> Result<(), MoriError> {
+        self.manage_player(net_id, "kick")
+    }
+
+    /// Pulls a player to the bot. See [`Bot::manage_player`].
+    pub fn pull_player(&self, net_id: u32) -> Result<(), MoriError> {
+        self.manage_player(net_id, "pull")
+    }
+
+    /// Clicks `button` on the popup that wrenching a player opens, answering
+    /// it directly:
+    ///
+    /// ```text
+    /// action|dialog_return
+    /// dialog_name|popup
+    /// netID|<net_id>|
+    /// buttonClicked|<kick|pull>
+    /// ```
+    ///
+    /// The server only offers these buttons to the world owner, so this fails
+    /// unless the bot owns the main lock and the player is in the world.
+    fn manage_player(&self, net_id: u32, button: &str) -> Result<(), MoriError> {
+        if !self.network.is_connected() {
+            return Err(MoriError::NotConnected);
+        }
+        if !self.owns_world() {
+            return Err(MoriError::NotWorldOwner);
+        }
+        if !self.world.players.lock().unwrap().contains_key(&net_id) {
+            return Err(MoriError::PlayerNotFound(net_id));
+        }
+
+        self.send_dialog_return(&format!(
+            "dialog_name|popup\nnetID|{}|\nbuttonClicked|{}",
+            net_id, button
+        ));
+        Ok(())
+    }
+
+    /// Selects the item `place` uses when a script doesn't name one. Fails if
+    /// the bot doesn't have it; `0` clears the selection.
+    pub fn hold_item(&self, item_id: u32) -> Result<(), MoriError> {
+        if item_id != 0 && !self.inventory.has_item(item_id as u16, 1) {
+            return Err(MoriError::ItemNotFound(item_id));
+        }
+        self.movement.state().held_item = item_id;
+        Ok(())
+    }
+
+    /// The held item, or `None` once nothing is held or the bot ran out of it.
+    pub fn held_item(&self) -> Option<u32> {
+        let held = self.movement.state().held_item;
+        (held != 0 && self.inventory.has_item(held as u16, 1)).then_some(held)
+    }
+
     pub fn wear(&self, item_id: u32) {
+        self.movement.state().held_item = item_id;
         let packet = NetGamePacketData {
             _type: NetGamePacket::ItemActivateRequest,
             value: item_id,
@@ -551,7 +1541,26 @@ impl Bot {
         self.send_game_packet(&packet, None, true);
     }
 
+    /// Wears the item if it isn't worn, otherwise takes it off; the server
+    /// toggles on the same request. Returns whether it is worn afterwards.
+    /// Items whose state is unknown are simply worn.
+    pub fn toggle_wear(&self, item_id: u32) -> bool {
+        let worn = !self.inventory.is_worn(item_id as u16).unwrap_or(false);
+        self.wear(item_id);
+        self.inventory.set_worn(item_id as u16, worn);
+        if !worn {
+            self.movement.state().held_item = 0;
+        }
+        worn
+    }
+
     pub fn walk(&self, x: i32, y: i32, ap: bool) {
+        self.walk_step(x, y, ap, !ap && y != 0);
+    }
+
+    /// Walk pacing follows the world's gravity for `vertical` steps.
+    fn walk_step(&self, x: i32, y: i32, ap: bool, vertical: bool) {
+        let _moving = self.movement.begin_move();
         if !ap {
             self.movement.translate((x * 32) as f32, (y * 32) as f32);
         }
@@ -577,8 +1586,10 @@ impl Bot {
         }
 
         self.send_game_packet(&pkt, None, false);
-        let delay = self.config.findpath_delay();
-        thread::sleep(Duration::from_millis(delay as u64));
+        let delay = self
+            .movement
+            .step_delay(self.config.findpath_delay(), vertical);
+        thread::sleep(delay);
     }
 
     pub fn find_path(&self, x: u32, y: u32) {
@@ -590,16 +1601,49 @@ impl Bot {
             astar.find_path((position.0 as u32) / 32, (position.1 as u32) / 32, x, y, has_access)
         };
 
+        let _moving = self.movement.begin_move();
         if let Some(paths) = &paths {
+            let mut last_y = (position.1 as u32) / 32;
             for node in paths {
                 self.movement
                     .set_position(node.x as f32 * 32.0, node.y as f32 * 32.0);
-                self.walk(node.x as i32, node.y as i32, true);
+                self.walk_step(node.x as i32, node.y as i32, true, node.y != last_y);
+                last_y = node.y;
+            }
+        }
+    }
+
+    /// Number of steps from the bot to `(x, y)`, or `None` if unreachable. Doesn't move the bot.
+    pub fn path_length(&self, x: u32, y: u32) -> Option<u32> {
+        let position = self.movement.position();
+        let has_access = self.has_access();
+
+        let mut astar = self.movement.astar();
+        astar
+            .find_path(
+                (position.0 as u32) / 32,
+                (position.1 as u32) / 32,
+                x,
+                y,
+                has_access,
+            )
+            .map(|paths| paths.len().saturating_sub(1) as u32)
+    }
+
+    /// Paths back to the active checkpoint, or the main door when none is set.
+    /// Returns `false` if the world has neither.
+    pub fn go_home(&self) -> bool {
+        match self.world.respawn_position() {
+            Some((x, y)) => {
+                self.find_path(x, y);
+                true
             }
+            None => false,
         }
     }
 
-    pub fn drop_item(&self, item_id: u32, amount: u32) {
+    pub fn drop_item(&self, source: ActionSource, item_id: u32, amount: u32) {
+        self.audit(source, || format!("drop {} x{}", item_id, amount));
         self.send_text_packet(
             NetMessage::GenericText,
             format!("action|drop\n|itemID|{}\n", item_id).as_bytes(),
@@ -624,7 +1668,182 @@ impl Bot {
         });
     }
 
-    pub fn trash_item(&self, item_id: u32, amount: u32) {
+    /// Places an item onto a storage tile (display box, vending machine,
+    /// storage box) and answers the amount dialog the server opens. Returns
+    /// `false` if the bot doesn't hold `amount` of the item, isn't in game or
+    /// the place couldn't be sent. A full storage fires `onDepositFailed(reason)` instead of confirming.
+    pub fn deposit_item(
+        &self,
+        source: ActionSource,
+        offset_x: i32,
+        offset_y: i32,
+        item_id: u32,
+        amount: u32,
+    ) -> bool {
+        self.audit(source, || {
+            format!("deposit {} x{} at {},{}", item_id, amount, offset_x, offset_y)
+        });
+        if amount == 0 || (self.inventory.get_item_count(item_id as u16) as u32) < amount {
+            return false;
+        }
+        // A queued place would open the dialog after the hook is gone.
+        if !self.is_ready() {
+            self.runtime.request_demand();
+            return false;
+        }
+
+        let position = self.movement.position();
+        let tile_x = (position.0 / 32.0).floor() as i32 + offset_x;
+        let tile_y = (position.1 / 32.0).floor() as i32 + offset_y;
+        {
+            let mut deposit = self.temporary_data.deposit.lock().unwrap();
+            let mut dialog_callback = self.temporary_data.dialog_callback.lock().unwrap();
+            *deposit = (tile_x, tile_y, item_id, amount);
+
+            *dialog_callback = Some(|bot| {
+                let raw = bot.temporary_data.last_dialog.lock().unwrap().clone();
+                let dialog = utils::dialog::Dialog::parse(&raw);
+                let full = dialog.reports_storage_full();
+
+                if full {
+                    bot.temporary_data
+                        .deposit_failed
+                        .store(true, Ordering::SeqCst);
+                } else if !dialog.name.is_empty() {
+                    let (x, y, id, amount) = *bot.temporary_data.deposit.lock().unwrap();
+                    bot.send_dialog_return(&format!(
+                        "dialog_name|{}\ntilex|{}|\ntiley|{}|\nitemID|{}|\ncount|{}",
+                        dialog.name, x, y, id, amount
+                    ));
+                }
+
+                *bot.temporary_data.deposit.lock().unwrap() = (0, 0, 0, 0);
+                *bot.temporary_data.dialog_callback.lock().unwrap() = None;
+
+                if full {
+                    bot.runtime
+                        .push_log("Deposit failed: storage is full".to_string());
+                    lua::invoke_callbacks(bot, "onDepositFailed", "full".to_string());
+                }
+            });
+        }
+
+        // Armed before sending so a quick dialog isn't missed, and disarmed
+        // again if nothing went out, so an unrelated dialog can't trigger it.
+        if !self.tile_action(offset_x, offset_y, item_id, false) {
+            *self.temporary_data.deposit.lock().unwrap() = (0, 0, 0, 0);
+            *self.temporary_data.dialog_callback.lock().unwrap() = None;
+            return false;
+        }
+        true
+    }
+
+    /// Deposits into the storage at tile `(x, y)` and waits for the server's
+    /// dialog to be answered.
+    fn deposit_and_wait(
+        &self,
+        source: ActionSource,
+        (x, y): (i32, i32),
+        item_id: u32,
+        amount: u32,
+    ) -> FarmStep {
+        const DIALOG_TIMEOUT: Duration = Duration::from_secs(5);
+
+        let position = self.movement.position();
+        let offset_x = x - (position.0 / 32.0).floor() as i32;
+        let offset_y = y - (position.1 / 32.0).floor() as i32;
+        if offset_x.abs() > 4 || offset_y.abs() > 4 {
+            return FarmStep::Stop(FarmStop::OutOfRange);
+        }
+
+        self.temporary_data
+            .deposit_failed
+            .store(false, Ordering::SeqCst);
+        if !self.deposit_item(source, offset_x, offset_y, item_id, amount) {
+            return FarmStep::Skipped;
+        }
+
+        let started = Instant::now();
+        while *self.temporary_data.deposit.lock().unwrap() != (0, 0, 0, 0) {
+            if started.elapsed() >= DIALOG_TIMEOUT {
+                *self.temporary_data.deposit.lock().unwrap() = (0, 0, 0, 0);
+                *self.temporary_data.dialog_callback.lock().unwrap() = None;
+                return FarmStep::Stop(FarmStop::Timeout);
+            }
+            thread::sleep(Duration::from_millis(50));
+        }
+
+        if self.temporary_data.deposit_failed.load(Ordering::SeqCst) {
+            FarmStep::Stop(FarmStop::Full)
+        } else {
+            FarmStep::Deposited
+        }
+    }
+
+    /// Collects nearby drops and deposits `config.items` into the container,
+    /// repeating until a cycle finds nothing, the container is full or
+    /// `max_cycles` is reached. Blocks the calling thread.
+    pub fn farm_cycle(&self, source: ActionSource, config: &FarmConfig) -> FarmReport {
+        let delay = Duration::from_millis(config.step_delay_ms);
+        let mut report = FarmReport {
+            stop: FarmStop::MaxCycles,
+            ..Default::default()
+        };
+
+        'cycles: while report.cycles.len() < config.max_cycles as usize {
+            if self.scripting.stop_requested.load(Ordering::SeqCst) {
+                report.stop = FarmStop::Stopped;
+                break;
+            }
+
+            let mut stats = FarmCycleStats {
+                collected: self.collect(source),
+                deposited: 0,
+            };
+            thread::sleep(delay);
+
+            for &item_id in &config.items {
+                let amount = self.inventory.get_item_count(item_id as u16) as u32;
+                if amount == 0 {
+                    continue;
+                }
+                match self.deposit_and_wait(source, config.container, item_id, amount) {
+                    FarmStep::Deposited => stats.deposited += amount,
+                    FarmStep::Skipped => {}
+                    FarmStep::Stop(stop) => {
+                        report.stop = stop;
+                        report.cycles.push(stats);
+                        break 'cycles;
+                    }
+                }
+                thread::sleep(delay);
+            }
+
+            self.runtime.push_log(format!(
+                "Farm cycle {}: collected {}, deposited {}",
+                report.cycles.len() + 1,
+                stats.collected,
+                stats.deposited
+            ));
+            report.cycles.push(stats);
+            if stats == FarmCycleStats::default() {
+                report.stop = FarmStop::Empty;
+                break;
+            }
+        }
+
+        self.runtime.push_log(format!(
+            "Farm stopped ({}): collected {}, deposited {} over {} cycles",
+            report.stop.as_str(),
+            report.collected(),
+            report.deposited(),
+            report.cycles.len()
+        ));
+        report
+    }
+
+    pub fn trash_item(&self, source: ActionSource, item_id: u32, amount: u32) {
+        self.audit(source, || format!("trash {} x{}", item_id, amount));
         self.send_text_packet(
             NetMessage::GenericText,
             format!("action|trash\n|itemID|{}\n", item_id).as_bytes(),
@@ -649,9 +1868,84 @@ impl Bot {
         });
     }
 
+    /// Opens a trade with a player through their wrench popup. The server
+    /// answers with `OnStartTrade` once they accept.
+    pub fn start_trade(&self, source: ActionSource, net_id: u32) {
+        self.audit(source, || format!("start trade {}", net_id));
+        self.send_dialog_return(&format!(
+            "dialog_name|popup\nnetID|{}|\nbuttonClicked|trade",
+            net_id
+        ));
+    }
+
+    /// Offers an item in the open trade and answers the amount dialog.
+    /// Fails without sending if no trade is open or the bot doesn't hold
+    /// `amount` of the item.
+    pub fn trade_add_item(&self, item_id: u32, amount: u32) -> Result<(), MoriError> {
+        if self.temporary_data.trade.lock().unwrap().is_none() {
+            return Err(MoriError::NoTrade);
+        }
+        if amount == 0 || (self.inventory.get_item_count(item_id as u16) as u32) < amount {
+            return Err(MoriError::ItemNotFound(item_id));
+        }
+
+        *self.temporary_data.trade_add.lock().unwrap() = (item_id, amount);
+        *self.temporary_data.dialog_callback.lock().unwrap() = Some(|bot| {
+            let mut trade_add = bot.temporary_data.trade_add.lock().unwrap();
+            let mut dialog_callback = bot.temporary_data.dialog_callback.lock().unwrap();
+            bot.send_dialog_return(&format!(
+                "dialog_name|trade_item\nitemID|{}|\ncount|{}",
+                trade_add.0, trade_add.1
+            ));
+            *trade_add = (0, 0);
+            *dialog_callback = None;
+        });
+        self.send_text_packet(
+            NetMessage::GenericText,
+            format!("action|mod_trade\nitemID|{}\n", item_id).as_bytes(),
+        );
+        Ok(())
+    }
+
+    /// Accepts the current offers. The confirmation dialog the server shows
+    /// once both sides have accepted is answered automatically.
+    pub fn trade_accept(&self, source: ActionSource) -> Result<(), MoriError> {
+        self.audit(source, || "accept trade".to_string());
+        {
+            let mut trade = self.temporary_data.trade.lock().unwrap();
+            match trade.as_mut() {
+                Some(trade) => trade.accepted = true,
+                None => return Err(MoriError::NoTrade),
+            }
+        }
+        self.send_text_packet(NetMessage::GenericText, b"action|trade_accept\nstatus|1\n");
+        Ok(())
+    }
+
+    pub fn trade_cancel(&self) {
+        self.send_text_packet(NetMessage::GenericText, b"action|trade_cancel\n");
+        *self.temporary_data.trade.lock().unwrap() = None;
+    }
+
+    /// Confirms the final trade dialog if the bot accepted the current offer.
+    pub(crate) fn handle_trade_dialog(&self, message: &str) {
+        let accepted = self
+            .temporary_data
+            .trade
+            .lock()
+            .unwrap()
+            .as_ref()
+            .is_some_and(|trade| trade.accepted);
+        let dialog = utils::dialog::Dialog::parse(message);
+        if accepted && dialog.name == "trade_confirm" {
+            let body = utils::dialog::dialog_return(&dialog.name, &dialog.embedded);
+            self.send_dialog_return(&body);
+        }
+    }
+
     pub fn accept_access(&self) {
         let net_id = self.runtime.net_id();
-        self.wrench_player(net_id);
+        self.send_wrench_player(net_id);
 
         let mut dialog_callback = self.temporary_data.dialog_callback.lock().unwrap();
         *dialog_callback = Some(|bot| {
@@ -674,17 +1968,18 @@ impl Bot {
     }
 
     pub fn has_access(&self) -> bool {
-        const LOCK_ITEM_IDS: &[u16] = &[242, 1796, 2408, 7188, 10410];
-
         let world_lock = match self.world.data.try_lock() {
             Ok(world) => world,
-            Err(_) => return false,
+            Err(_) => {
+                self.runtime.record_world_lock_miss();
+                return false;
+            }
         };
 
         let bot_uid = self.runtime.user_id();
 
         for tile in &world_lock.tiles {
-            if LOCK_ITEM_IDS.contains(&tile.foreground_item_id) {
+            if game_world::WORLD_LOCK_IDS.contains(&tile.foreground_item_id) {
                 if let gtworld_r::TileType::Lock { access_uids, .. } = &tile.tile_type {
                     return access_uids.contains(&bot_uid);
                 }
@@ -694,11 +1989,29 @@ impl Bot {
         false
     }
 
-    pub fn collect(&self) -> usize {
+    pub fn collect(&self, source: ActionSource) -> usize {
+        self.collect_with(source, self.config.collect_strategy())
+    }
+
+    /// Picks up nearby drops and returns how many were collected. Only
+    /// collections that picked something up are audited, so the auto-collect
+    /// poll doesn't flood the log.
+    pub fn collect_with(&self, source: ActionSource, strategy: CollectStrategy) -> usize {
+        let collected = self.collect_drops(strategy);
+        if collected > 0 {
+            self.audit(source, || format!("collect {} items", collected));
+        }
+        collected
+    }
+
+    fn collect_drops(&self, strategy: CollectStrategy) -> usize {
         let is_in_world = {
             match self.world.data.try_lock() {
                 Ok(world) => world.name != "EXIT",
-                Err(_) => return 0,
+                Err(_) => {
+                    self.runtime.record_world_lock_miss();
+                    return 0;
+                }
             }
         };
 
@@ -716,13 +2029,19 @@ impl Bot {
 
         let inventory_snapshot = match self.inventory.try_get_snapshot() {
             Some(snapshot) => snapshot,
-            None => return 0,
+            None => {
+                self.runtime.record_inventory_lock_miss();
+                return 0;
+            }
         };
 
         let inventory_size = inventory_snapshot.size;
         let current_item_count = inventory_snapshot.item_count;
         let item_amounts = inventory_snapshot.item_amounts;
 
+        let radius = self.config.collect_radius();
+        let filter = self.config.collect_filter();
+
         let collectible_items = {
             match self.world.data.try_lock() {
                 Ok(world) => {
@@ -730,23 +2049,29 @@ impl Bot {
                         Vec::with_capacity(std::cmp::min(world.dropped.items.len(), 50));
 
                     for item in &world.dropped.items {
+                        if filter.as_ref().is_some_and(|ids| !ids.contains(&item.id)) {
+                            continue;
+                        }
+
                         let dx = bot_tile_x - item.x;
                         let dy = bot_tile_y - item.y;
                         let distance_squared = dx * dx + dy * dy;
 
-                        if distance_squared <= 9216.0 { // 3 tile radius (32*3)^2 = 96^2
+                        if strategy.in_range(radius, distance_squared) {
                             nearby_items.push((item.clone(), distance_squared));
                         }
                     }
 
-                    nearby_items
-                        .sort_by(|a, b| a.1.partial_cmp(&b.1).unwrap_or(std::cmp::Ordering::Equal));
+                    strategy.order(&mut nearby_items);
                     nearby_items
                         .into_iter()
                         .map(|(item, _)| item)
                         .collect::<Vec<_>>()
                 }
-                Err(_) => return 0,
+                Err(_) => {
+                    self.runtime.record_world_lock_miss();
+                    return 0;
+                }
             }
         };
 
@@ -785,6 +2110,41 @@ impl Bot {
         collected_count
     }
 
+    /// Walks to the dropped item `uid` and picks it up, waiting up to a second
+    /// for the server to remove it. Returns whether the item is still on the
+    /// ground afterwards, so `false` means it was collected or already gone.
+    pub fn collect_item(&self, uid: u32) -> bool {
+        const PICKUP_TIMEOUT: Duration = Duration::from_secs(1);
+
+        let item_exists = |bot: &Self| {
+            let world = bot.world.data.lock().unwrap();
+            world.dropped.items.iter().any(|item| item.uid == uid)
+        };
+        let position = {
+            let world = self.world.data.lock().unwrap();
+            match world.dropped.items.iter().find(|item| item.uid == uid) {
+                Some(item) => (item.x, item.y),
+                None => return false,
+            }
+        };
+
+        self.find_path((position.0 / 32.0) as u32, (position.1 / 32.0) as u32);
+        let packet = NetGamePacketData {
+            _type: NetGamePacket::ItemActivateObjectRequest,
+            vector_x: position.0,
+            vector_y: position.1,
+            value: uid,
+            ..Default::default()
+        };
+        self.send_game_packet(&packet, None, true);
+
+        let started = Instant::now();
+        while item_exists(self) && started.elapsed() < PICKUP_TIMEOUT {
+            thread::sleep(Duration::from_millis(50));
+        }
+        item_exists(self)
+    }
+
     pub fn enter_door(&self, offset_x: i32, offset_y: i32) {
         let position = self.movement.position();
         let base_x = (position.0 / 32.0).floor() as i32;
@@ -804,6 +2164,37 @@ impl Bot {
         self.send_game_packet(&pkt, None, true);
     }
 
+    /// Cached friends list; empty until `refresh_friends` has received the dialog.
+    pub fn friends(&self) -> Vec<Friend> {
+        self.temporary_data
+            .friends
+            .lock()
+            .unwrap()
+            .clone()
+            .unwrap_or_default()
+    }
+
+    /// Opens the social portal so the friends dialog gets parsed into the cache.
+    pub fn refresh_friends(&self) {
+        self.temporary_data
+            .friends_requested
+            .store(true, Ordering::SeqCst);
+        self.send_text_packet(NetMessage::GenericText, b"action|friends\n");
+    }
+
+    /// Answers a dialog on behalf of a script or API caller, recording it in
+    /// the audit log. Replies the bot sends by itself use `send_dialog_return`.
+    pub fn send_dialog(&self, source: ActionSource, dialog_data: &str) {
+        self.audit(source, || {
+            let name = dialog_data
+                .lines()
+                .find_map(|line| line.strip_prefix("dialog_name|"))
+                .unwrap_or("");
+            format!("send dialog {}", name)
+        });
+        self.send_dialog_return(dialog_data);
+    }
+
     pub fn send_dialog_return(&self, dialog_data: &str) {
         self.send_text_packet(
             NetMessage::GenericText,
@@ -820,23 +2211,7 @@ pub fn test_socks5_proxy(socks5_config: &Socks5Config) -> (bool, bool) {
 }
 
 fn test_server_data_fetch(socks5_config: &Socks5Config) -> bool {
-    let proxy_url = if let (Some(username), Some(password)) =
-        (&socks5_config.username, &socks5_config.password)
-    {
-        format!(
-            "socks5://{}:{}@{}:{}",
-            username,
-            password,
-            socks5_config.proxy_addr.ip(),
-            socks5_config.proxy_addr.port()
-        )
-    } else {
-        format!(
-            "socks5://{}:{}",
-            socks5_config.proxy_addr.ip(),
-            socks5_config.proxy_addr.port()
-        )
-    };
+    let proxy_url = socks5_config.url();
 
     let login_info = LoginInfo::new();
     match server::get_server_data_with_proxy(false, &login_info, Some(&proxy_url)) {
@@ -0,0 +1,116 @@
+use std::collections::VecDeque;
+use std::sync::Mutex;
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// Default fleet-wide ceiling: this many logins per window.
+pub const DEFAULT_MAX_LOGINS: usize = 10;
+pub const DEFAULT_WINDOW: Duration = Duration::from_secs(60);
+
+/// Process-wide throttle every bot consults before connecting.
+static THROTTLE: LoginThrottle = LoginThrottle::new();
+
+/// Limits logins across all bots to `max_logins` per `window`.
+pub fn set_global_login_rate(max_logins: usize, window: Duration) {
+    THROTTLE.set_rate(max_logins, window);
+}
+
+pub fn global_login_rate() -> (usize, Duration) {
+    THROTTLE.rate()
+}
+
+pub(crate) fn acquire() {
+    THROTTLE.acquire();
+}
+
+#[derive(Debug)]
+struct ThrottleState {
+    max_logins: usize,
+    window: Duration,
+    recent: VecDeque<Instant>,
+}
+
+/// Sliding-window limiter: a login is allowed once fewer than `max_logins`
+/// happened in the last `window`.
+#[derive(Debug)]
+pub struct LoginThrottle {
+    state: Mutex<ThrottleState>,
+}
+
+impl LoginThrottle {
+    pub const fn new() -> Self {
+        Self {
+            state: Mutex::new(ThrottleState {
+                max_logins: DEFAULT_MAX_LOGINS,
+                window: DEFAULT_WINDOW,
+                recent: VecDeque::new(),
+            }),
+        }
+    }
+
+    pub fn set_rate(&self, max_logins: usize, window: Duration) {
+        let mut state = self.state.lock().unwrap();
+        state.max_logins = max_logins.max(1);
+        state.window = window;
+    }
+
+    pub fn rate(&self) -> (usize, Duration) {
+        let state = self.state.lock().unwrap();
+        (state.max_logins, state.window)
+    }
+
+    /// Records a login at `now` if allowed, otherwise returns how long to wait.
+    fn try_acquire(&self, now: Instant) -> Result<(), Duration> {
+        let mut state = self.state.lock().unwrap();
+        let window = state.window;
+        while state
+            .recent
+            .front()
+            .is_some_and(|&at| now.duration_since(at) >= window)
+        {
+            state.recent.pop_front();
+        }
+
+        if state.recent.len() < state.max_logins {
+            state.recent.push_back(now);
+            Ok(())
+        } else {
+            let oldest = state.recent[0];
+            Err(window - now.duration_since(oldest))
+        }
+    }
+
+    pub fn acquire(&self) {
+        while let Err(wait) = self.try_acquire(Instant::now()) {
+            thread::sleep(wait.max(Duration::from_millis(10)));
+        }
+    }
+}
+
+impl Default for LoginThrottle {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_limits_logins_per_window() {
+        let throttle = LoginThrottle::new();
+        throttle.set_rate(2, Duration::from_secs(10));
+        let now = Instant::now();
+
+        assert!(throttle.try_acquire(now).is_ok());
+        assert!(throttle.try_acquire(now + Duration::from_secs(1)).is_ok());
+        assert_eq!(
+            throttle.try_acquire(now + Duration::from_secs(4)),
+            Err(Duration::from_secs(6))
+        );
+
+        // The first login falls out of the window.
+        assert!(throttle.try_acquire(now + Duration::from_secs(10)).is_ok());
+    }
+}
@@ -1,8 +1,31 @@
-use std::sync::Arc;
+use std::collections::HashMap;
+use std::sync::atomic::Ordering;
+use std::sync::{Arc, OnceLock};
+use std::time::{Duration, Instant};
 
-use crate::types::bot::{BotArc, LuaGamePacket};
-use crate::types::net_game_packet::{NetGamePacket, NetGamePacketData};
 use crate::Bot;
+use crate::script_storage::{self, ScriptStorage};
+use crate::server;
+use crate::types::bot::{BotArc, LoginVia, LuaGamePacket, LuaTimer};
+use crate::types::net_game_packet::{NetGamePacket, NetGamePacketData};
+use crate::utils::dialog::Dialog;
+use crate::utils::variant::{Variant, VariantList};
+
+/// Reference point for the monotonic clock used by `waitUntil`.
+static CLOCK_START: OnceLock<Instant> = OnceLock::new();
+
+/// Registry names of the task scheduler's step and cancel functions.
+const TASK_STEP: &str = "mori.tasks.step";
+const TASK_CANCEL: &str = "mori.tasks.cancel";
+
+/// Callback slot `bot:onDialog` fills; it holds at most one handler.
+pub(crate) const DIALOG_HANDLER: &str = "onDialog";
+
+/// Error message a script is aborted with after `stop_script`.
+const SCRIPT_STOPPED: &str = "script stopped";
+
+/// Default timeout for `http.get`/`http.post`.
+const HTTP_TIMEOUT_MS: u64 = 10_000;
 
 pub fn initialize(bot: &Arc<Bot>) {
     let bot_arc = BotArc(bot.clone());
@@ -14,15 +37,261 @@ pub fn initialize(bot: &Arc<Bot>) {
         .unwrap();
     lua.globals().set("getBot", get_bot).unwrap();
 
+    // Aborts the running script once stop_script is called, checked every
+    // few thousand instructions and while sleeping.
+    let hook_bot = Arc::downgrade(bot);
+    lua.set_hook(
+        mlua::HookTriggers::new().every_nth_instruction(1000),
+        move |_, _| match hook_bot.upgrade() {
+            Some(bot) if bot.scripting.stop_requested.load(Ordering::SeqCst) => {
+                Err(mlua::Error::RuntimeError(SCRIPT_STOPPED.to_string()))
+            }
+            _ => Ok(mlua::VmState::Continue),
+        },
+    );
+
     // sleep(ms)
+    let sleep_bot = Arc::downgrade(bot);
     let sleep = lua
         .create_function(move |_, duration: u64| {
-            std::thread::sleep(std::time::Duration::from_millis(duration));
-            Ok(())
+            const SLICE: Duration = Duration::from_millis(50);
+            let deadline = Instant::now() + Duration::from_millis(duration);
+            loop {
+                if sleep_bot
+                    .upgrade()
+                    .is_some_and(|bot| bot.scripting.stop_requested.load(Ordering::SeqCst))
+                {
+                    return Err(mlua::Error::RuntimeError(SCRIPT_STOPPED.to_string()));
+                }
+                let remaining = deadline.saturating_duration_since(Instant::now());
+                if remaining.is_zero() {
+                    return Ok(());
+                }
+                std::thread::sleep(remaining.min(SLICE));
+            }
         })
         .unwrap();
     lua.globals().set("sleep", sleep).unwrap();
 
+    // stopScript()
+    let stop_bot = Arc::downgrade(bot);
+    let stop_script = lua
+        .create_function(move |_, ()| Ok(stop_bot.upgrade().is_some_and(|bot| bot.stop_script())))
+        .unwrap();
+    lua.globals().set("stopScript", stop_script).unwrap();
+
+    // waitUntil(predicate, timeoutMs?, intervalMs?) -> bool
+    // Inside a coroutine it yields until the next check, like wait(), so the
+    // caller's loop keeps running; otherwise it sleeps for the interval.
+    let now_ms = lua
+        .create_function(move |_, ()| {
+            let started = *CLOCK_START.get_or_init(Instant::now);
+            Ok(started.elapsed().as_millis() as u64)
+        })
+        .unwrap();
+    let wait_until: mlua::Function = lua
+        .load(
+            r#"
+            local now = ...
+            return function(predicate, timeout_ms, interval_ms)
+                local interval = interval_ms or 100
+                local deadline = timeout_ms and (now() + timeout_ms)
+                while true do
+                    if predicate() then
+                        return true
+                    end
+                    if deadline and now() >= deadline then
+                        return false
+                    end
+                    if coroutine.isyieldable() then
+                        coroutine.yield(now() + interval)
+                    else
+                        sleep(interval)
+                    end
+                end
+            end
+            "#,
+        )
+        .set_name("waitUntil")
+        .call(now_ms.clone())
+        .unwrap();
+    lua.globals().set("waitUntil", wait_until).unwrap();
+
+    // spawn(fn, ...) -> task, wait(ms)
+    // Tasks are coroutines resumed by the scheduler thread. Scheduling is
+    // cooperative: a task only gives way to others when it calls wait() or
+    // waitUntil(), and none run while another Lua call (such as a blocking
+    // sleep in the main script) holds the state.
+    let (spawn, wait, step, cancel): (
+        mlua::Function,
+        mlua::Function,
+        mlua::Function,
+        mlua::Function,
+    ) = lua
+        .load(
+            r#"
+            local now = ...
+            local tasks = {}
+
+            local function spawn(fn, ...)
+                local task = { co = coroutine.create(fn), wake = 0, args = table.pack(...) }
+                tasks[#tasks + 1] = task
+                return task.co
+            end
+
+            local function wait(ms)
+                if coroutine.isyieldable() then
+                    coroutine.yield(now() + (ms or 0))
+                else
+                    sleep(ms or 0)
+                end
+            end
+
+            local function step()
+                local errors = {}
+                local t = now()
+                local running = tasks
+                tasks = {}
+                for _, task in ipairs(running) do
+                    local keep = true
+                    if t >= task.wake then
+                        local args = task.args
+                        task.args = { n = 0 }
+                        local ok, wake = coroutine.resume(task.co, table.unpack(args, 1, args.n))
+                        if not ok then
+                            errors[#errors + 1] = tostring(wake)
+                            keep = false
+                        elseif coroutine.status(task.co) == "dead" then
+                            keep = false
+                        else
+                            task.wake = type(wake) == "number" and wake or 0
+                        end
+                    end
+                    if keep then
+                        tasks[#tasks + 1] = task
+                    end
+                end
+                return errors
+            end
+
+            local function cancel()
+                tasks = {}
+            end
+
+            return spawn, wait, step, cancel
+            "#,
+        )
+        .set_name("tasks")
+        .call(now_ms)
+        .unwrap();
+    lua.globals().set("spawn", spawn).unwrap();
+    lua.globals().set("wait", wait).unwrap();
+    lua.set_named_registry_value(TASK_STEP, step).unwrap();
+    lua.set_named_registry_value(TASK_CANCEL, cancel).unwrap();
+
+    // setTimeout(fn, ms) / setInterval(fn, ms) -> id, clearTimeout(id) / clearInterval(id)
+    for (name, repeat) in [("setTimeout", false), ("setInterval", true)] {
+        let timer_bot = bot.clone();
+        let set_timer = lua
+            .create_function(move |lua, (func, ms): (mlua::Function, u64)| {
+                let key = Arc::new(lua.create_registry_value(func)?);
+                let delay = Duration::from_millis(ms);
+                let id = timer_bot
+                    .scripting
+                    .next_timer_id
+                    .fetch_add(1, Ordering::Relaxed);
+                timer_bot.scripting.timers.lock().unwrap().insert(
+                    id,
+                    LuaTimer {
+                        key,
+                        interval: repeat.then_some(delay.max(Duration::from_millis(1))),
+                        next_fire: Instant::now() + delay,
+                    },
+                );
+                Ok(id)
+            })
+            .unwrap();
+        lua.globals().set(name, set_timer).unwrap();
+    }
+    for name in ["clearTimeout", "clearInterval"] {
+        let timer_bot = bot.clone();
+        let clear_timer = lua
+            .create_function(move |lua, id: u32| {
+                let removed = timer_bot.scripting.timers.lock().unwrap().remove(&id);
+                if let Some(timer) = removed {
+                    release_key(lua, timer.key)?;
+                }
+                Ok(())
+            })
+            .unwrap();
+        lua.globals().set(name, clear_timer).unwrap();
+    }
+    spawn_scheduler_thread(bot);
+
+    // http.get(url, headers?, timeoutMs?) / http.post(url, body, headers?, timeoutMs?)
+    //   -> { status, body }, raising on network errors
+    type Headers = Option<HashMap<String, String>>;
+    let http = lua.create_table().unwrap();
+    let get_bot = bot.clone();
+    let http_get = lua
+        .create_function(
+            move |lua, (url, headers, timeout_ms): (String, Headers, Option<u64>)| {
+                http_lua_request(lua, &get_bot, &url, None, headers, timeout_ms)
+            },
+        )
+        .unwrap();
+    http.set("get", http_get).unwrap();
+    let post_bot = bot.clone();
+    let http_post = lua
+        .create_function(
+            move |lua, (url, body, headers, timeout_ms): (String, String, Headers, Option<u64>)| {
+                http_lua_request(lua, &post_bot, &url, Some(&body), headers, timeout_ms)
+            },
+        )
+        .unwrap();
+    http.set("post", http_post).unwrap();
+    lua.globals().set("http", http).unwrap();
+
+    // storage.get(key) / storage.set(key, value) / storage.delete(key), persisted per bot
+    let storage = lua.create_table().unwrap();
+    let get_bot = bot.clone();
+    let storage_get = lua
+        .create_function(move |lua, key: String| {
+            with_storage(&get_bot, |storage| match storage.get(&key) {
+                Some(value) => script_storage::json_to_lua(lua, value),
+                None => Ok(mlua::Value::Nil),
+            })
+        })
+        .unwrap();
+    storage.set("get", storage_get).unwrap();
+    let set_bot = bot.clone();
+    let storage_set = lua
+        .create_function(move |_, (key, value): (String, mlua::Value)| {
+            let value = match value {
+                mlua::Value::Nil => None,
+                value => Some(script_storage::lua_to_json(&value)?),
+            };
+            with_storage(&set_bot, |storage| {
+                match value {
+                    Some(value) => storage.set(key, value),
+                    None => storage.delete(&key),
+                }
+                .map_err(mlua::Error::external)
+            })
+        })
+        .unwrap();
+    storage.set("set", storage_set).unwrap();
+    let delete_bot = bot.clone();
+    let storage_delete = lua
+        .create_function(move |_, key: String| {
+            with_storage(&delete_bot, |storage| {
+                storage.delete(&key).map_err(mlua::Error::external)
+            })
+        })
+        .unwrap();
+    storage.set("delete", storage_delete).unwrap();
+    lua.globals().set("storage", storage).unwrap();
+
     // log(message)
     let log_bot = bot.clone();
     let log_fn = lua
@@ -78,6 +347,91 @@ pub fn initialize(bot: &Arc<Bot>) {
         .set("getItemInfoByName", get_item_info_by_name)
         .unwrap();
 
+    // parseDialog(raw) -> { dialog_name, labels, buttons, inputs, checkboxes, values }
+    let parse_dialog = lua
+        .create_function(|lua, raw: String| {
+            let dialog = Dialog::parse(&raw);
+            let t = lua.create_table()?;
+            t.set("dialog_name", dialog.name.clone())?;
+            t.set("labels", dialog.labels.clone())?;
+
+            // Pre-filled with what the server would get back if the dialog
+            // were confirmed untouched, so scripts only change what they need.
+            let values = lua.create_table()?;
+            for (key, value) in &dialog.embedded {
+                values.set(key.as_str(), value.as_str())?;
+            }
+
+            let buttons = lua.create_table()?;
+            for button in &dialog.buttons {
+                let entry = lua.create_table()?;
+                entry.set("name", button.name.as_str())?;
+                entry.set("label", button.label.as_str())?;
+                buttons.push(entry)?;
+            }
+            let inputs = lua.create_table()?;
+            for input in &dialog.inputs {
+                let entry = lua.create_table()?;
+                entry.set("name", input.name.as_str())?;
+                entry.set("label", input.label.as_str())?;
+                entry.set("default", input.default.as_str())?;
+                entry.set("maxLength", input.max_length)?;
+                inputs.push(entry)?;
+                values.set(input.name.as_str(), input.default.as_str())?;
+            }
+            let checkboxes = lua.create_table()?;
+            for checkbox in &dialog.checkboxes {
+                let entry = lua.create_table()?;
+                entry.set("name", checkbox.name.as_str())?;
+                entry.set("label", checkbox.label.as_str())?;
+                entry.set("checked", checkbox.checked)?;
+                checkboxes.push(entry)?;
+                values.set(checkbox.name.as_str(), checkbox.checked)?;
+            }
+
+            t.set("buttons", buttons)?;
+            t.set("inputs", inputs)?;
+            t.set("checkboxes", checkboxes)?;
+            t.set("values", values)?;
+            Ok(t)
+        })
+        .unwrap();
+    lua.globals().set("parseDialog", parse_dialog).unwrap();
+
+    // decodeVariant(bytes) -> { {type=..., value=...}, ... }
+    let decode_variant = lua
+        .create_function(|lua, data: mlua::String| {
+            let list = VariantList::deserialize(&data.as_bytes())
+                .map_err(|e| mlua::Error::RuntimeError(format!("invalid variant list: {}", e)))?;
+            let t = lua.create_table()?;
+            for index in 0..list.len() {
+                if let Some(variant) = list.get(index) {
+                    t.push(variant_to_lua(lua, variant)?)?;
+                }
+            }
+            Ok(t)
+        })
+        .unwrap();
+    lua.globals().set("decodeVariant", decode_variant).unwrap();
+
+    // encodeVariant({ "OnConsoleMessage", {type="uint", value=5}, ... }) -> bytes
+    let encode_variant = lua
+        .create_function(|lua, values: mlua::Table| {
+            let mut variants = Vec::new();
+            for (index, value) in values.sequence_values::<mlua::Value>().enumerate() {
+                variants.push(variant_from_lua(index + 1, value?)?);
+            }
+            if variants.len() > u8::MAX as usize {
+                return Err(mlua::Error::RuntimeError(format!(
+                    "a variant list holds at most {} values",
+                    u8::MAX
+                )));
+            }
+            lua.create_string(VariantList::from(variants).serialize())
+        })
+        .unwrap();
+    lua.globals().set("encodeVariant", encode_variant).unwrap();
+
     // GamePacket(type?) -> GamePacket
     let game_packet_ctor = lua
         .create_function(move |_, pkt_type: Option<u8>| {
@@ -91,36 +445,275 @@ pub fn initialize(bot: &Arc<Bot>) {
     lua.globals().set("GamePacket", game_packet_ctor).unwrap();
 }
 
-/// Invokes all registered Lua callbacks for the given event name with the provided arguments.
-/// Removes one-shot callbacks after invocation.
-pub fn invoke_callbacks<A: mlua::IntoLuaMulti + Clone>(bot: &Bot, event: &str, args: A) {
-    let lua = &bot.scripting.lua;
-    let mut cbs = bot.scripting.callbacks.lock().unwrap();
+/// Runs `f` on the bot's script storage, loading it on first use. The file is
+/// named after the GrowID, falling back to the login name and then the uuid.
+/// A store opened before the GrowID arrived moves to the GrowID's file the
+/// next time it's used, so every run ends up on the same file.
+fn with_storage<T>(
+    bot: &Bot,
+    f: impl FnOnce(&mut ScriptStorage) -> mlua::Result<T>,
+) -> mlua::Result<T> {
+    let key = {
+        let info = bot.auth.login_info();
+        let (name, uuid) = info
+            .as_ref()
+            .map(|info| (info.tank_id_name.clone(), info.uuid.clone()))
+            .unwrap_or_default();
+        match bot.auth.login_via() {
+            _ if !name.is_empty() => name,
+            LoginVia::LEGACY([username, _]) if !username.is_empty() => username,
+            _ if !uuid.is_empty() => uuid,
+            _ => "default".to_string(),
+        }
+    };
+    let path = ScriptStorage::path_for(&key);
+
+    let mut storage = bot.scripting.storage.lock().unwrap();
+    match storage.as_mut() {
+        Some(open) if open.path() != path.as_path() => open
+            .move_to(&path)
+            .map_err(|e| mlua::Error::RuntimeError(format!("failed to move storage: {}", e)))?,
+        Some(_) => {}
+        None => {
+            let loaded = ScriptStorage::load(&path)
+                .map_err(|e| mlua::Error::RuntimeError(format!("failed to load storage: {}", e)))?;
+            *storage = Some(loaded);
+        }
+    }
+    f(storage.as_mut().unwrap())
+}
+
+fn http_lua_request(
+    lua: &mlua::Lua,
+    bot: &Bot,
+    url: &str,
+    body: Option<&str>,
+    headers: Option<HashMap<String, String>>,
+    timeout_ms: Option<u64>,
+) -> mlua::Result<mlua::Table> {
+    let headers: Vec<(String, String)> = headers.unwrap_or_default().into_iter().collect();
+    let timeout = Duration::from_millis(timeout_ms.unwrap_or(HTTP_TIMEOUT_MS));
+    let (status, body) =
+        server::http_request(url, body, &headers, bot.proxy_url().as_deref(), timeout)
+            .map_err(|e| mlua::Error::RuntimeError(format!("HTTP request failed: {}", e)))?;
+
+    let t = lua.create_table()?;
+    t.set("status", status)?;
+    t.set("body", body)?;
+    Ok(t)
+}
+
+fn variant_to_lua(lua: &mlua::Lua, variant: &Variant) -> mlua::Result<mlua::Table> {
+    let entry = lua.create_table()?;
+    match variant {
+        Variant::Float(value) => {
+            entry.set("type", "float")?;
+            entry.set("value", *value)?;
+        }
+        Variant::String(value) => {
+            entry.set("type", "string")?;
+            entry.set("value", value.as_str())?;
+        }
+        Variant::Vec2((x, y)) => {
+            let value = lua.create_table()?;
+            value.set("x", *x)?;
+            value.set("y", *y)?;
+            entry.set("type", "vec2")?;
+            entry.set("value", value)?;
+        }
+        Variant::Vec3((x, y, z)) => {
+            let value = lua.create_table()?;
+            value.set("x", *x)?;
+            value.set("y", *y)?;
+            value.set("z", *z)?;
+            entry.set("type", "vec3")?;
+            entry.set("value", value)?;
+        }
+        Variant::Unsigned(value) => {
+            entry.set("type", "uint")?;
+            entry.set("value", *value)?;
+        }
+        Variant::Signed(value) => {
+            entry.set("type", "int")?;
+            entry.set("value", *value)?;
+        }
+        Variant::Unknown => entry.set("type", "unknown")?,
+    }
+    Ok(entry)
+}
 
-    if let Some(callbacks) = cbs.get_mut(event) {
-        let mut to_remove = Vec::new();
+/// Accepts the tables `decodeVariant` returns, plus bare strings and numbers
+/// for the common cases: integers encode as `int`, other numbers as `float`.
+fn variant_from_lua(index: usize, value: mlua::Value) -> mlua::Result<Variant> {
+    let invalid = |what: String| mlua::Error::RuntimeError(format!("variant {}: {}", index, what));
 
-        for (i, cb) in callbacks.iter().enumerate() {
-            if let Ok(func) = lua.registry_value::<mlua::Function>(&cb.key) {
-                if let Err(e) = func.call::<()>(args.clone()) {
+    match value {
+        mlua::Value::String(s) => Ok(Variant::String(s.to_string_lossy())),
+        mlua::Value::Integer(i) => i32::try_from(i)
+            .map(Variant::Signed)
+            .map_err(|_| invalid(format!("{} doesn't fit in an int", i))),
+        mlua::Value::Number(n) => Ok(Variant::Float(n as f32)),
+        mlua::Value::Table(t) => {
+            let kind: String = t
+                .get("type")
+                .map_err(|_| invalid("missing 'type'".to_string()))?;
+            let number = |key: &str| -> mlua::Result<f32> {
+                t.get::<mlua::Table>("value")
+                    .and_then(|v| v.get(key))
+                    .map_err(|_| invalid(format!("{} needs value.{}", kind, key)))
+            };
+            let missing = || invalid(format!("{} needs a value", kind));
+            match kind.as_str() {
+                "float" => Ok(Variant::Float(t.get("value").map_err(|_| missing())?)),
+                "string" => Ok(Variant::String(t.get("value").map_err(|_| missing())?)),
+                "vec2" => Ok(Variant::Vec2((number("x")?, number("y")?))),
+                "vec3" => Ok(Variant::Vec3((number("x")?, number("y")?, number("z")?))),
+                "uint" => Ok(Variant::Unsigned(t.get("value").map_err(|_| missing())?)),
+                "int" => Ok(Variant::Signed(t.get("value").map_err(|_| missing())?)),
+                "unknown" => Ok(Variant::Unknown),
+                other => Err(invalid(format!("unknown type '{}'", other))),
+            }
+        }
+        other => Err(invalid(format!("can't encode a {}", other.type_name()))),
+    }
+}
+
+/// Drives `setTimeout`/`setInterval` and `spawn`ed tasks. Due timers' keys
+/// are collected under the timers lock, and the Lua state is only touched
+/// after releasing it: scripts take the timers lock while holding the Lua
+/// state, so doing it the other way round would deadlock.
+fn spawn_scheduler_thread(bot: &Arc<Bot>) {
+    const TICK: Duration = Duration::from_millis(10);
+    let weak = Arc::downgrade(bot);
+
+    std::thread::spawn(move || {
+        loop {
+            let Some(bot) = weak.upgrade() else {
+                break;
+            };
+            if !bot.runtime.is_running() {
+                break;
+            }
+
+            let lua = &bot.scripting.lua;
+            let now = Instant::now();
+            let mut due = Vec::new();
+            let mut expired = Vec::new();
+            {
+                let mut timers = bot.scripting.timers.lock().unwrap();
+                let due_ids: Vec<u32> = timers
+                    .iter()
+                    .filter(|(_, timer)| timer.next_fire <= now)
+                    .map(|(id, _)| *id)
+                    .collect();
+
+                for id in due_ids {
+                    let Some(timer) = timers.get_mut(&id) else {
+                        continue;
+                    };
+                    due.push((id, Arc::clone(&timer.key)));
+                    match timer.interval {
+                        Some(interval) => timer.next_fire = now + interval,
+                        None => expired.extend(timers.remove(&id)),
+                    }
+                }
+            }
+
+            for (id, key) in due {
+                let Ok(func) = lua.registry_value::<mlua::Function>(&key) else {
+                    continue;
+                };
+                if let Err(e) = func.call::<()>(()) {
                     bot.runtime
-                        .push_log(format!("[Lua] Error in '{}' callback: {}", event, e));
+                        .push_log(format!("[Lua] Error in timer {}: {}", id, e));
                 }
-                if cb.once {
-                    to_remove.push(i);
+            }
+            for timer in expired {
+                let _ = release_key(lua, timer.key);
+            }
+
+            if let Ok(step) = lua.named_registry_value::<mlua::Function>(TASK_STEP) {
+                match step.call::<Vec<String>>(()) {
+                    Ok(errors) => {
+                        for e in errors {
+                            bot.runtime.push_log(format!("[Lua] Error in task: {}", e));
+                        }
+                    }
+                    Err(e) => bot
+                        .runtime
+                        .push_log(format!("[Lua] Task scheduler failed: {}", e)),
                 }
             }
-        }
 
-        // Remove once-callbacks in reverse order to maintain indices
-        for i in to_remove.into_iter().rev() {
-            let removed = callbacks.remove(i);
-            let _ = lua.remove_registry_value(removed.key);
+            drop(bot);
+            std::thread::sleep(TICK);
         }
+    });
+}
 
+/// Invokes all registered Lua callbacks for the given event name with the provided arguments.
+/// Removes one-shot callbacks after invocation. The callbacks lock is only held
+/// while the keys are collected, so a callback may fire events or register
+/// listeners itself.
+pub fn invoke_callbacks<A: mlua::IntoLuaMulti + Clone>(bot: &Bot, event: &str, args: A) {
+    let lua = &bot.scripting.lua;
+    let (keys, fired_once) = {
+        let mut cbs = bot.scripting.callbacks.lock().unwrap();
+        let Some(callbacks) = cbs.get_mut(event) else {
+            return;
+        };
+        let keys: Vec<_> = callbacks.iter().map(|cb| Arc::clone(&cb.key)).collect();
+        let (fired_once, kept): (Vec<_>, Vec<_>) = callbacks.drain(..).partition(|cb| cb.once);
+        *callbacks = kept;
         if callbacks.is_empty() {
             cbs.remove(event);
         }
+        (keys, fired_once)
+    };
+
+    for key in keys {
+        if let Ok(func) = lua.registry_value::<mlua::Function>(&key) {
+            if let Err(e) = func.call::<()>(args.clone()) {
+                bot.runtime
+                    .push_log(format!("[Lua] Error in '{}' callback: {}", event, e));
+            }
+        }
+    }
+    for cb in fired_once {
+        let _ = release_key(lua, cb.key);
+    }
+}
+
+/// Frees a callback's or timer's registry slot. If a call in flight still
+/// holds a clone of the key, the slot is freed once that clone is dropped.
+pub(crate) fn release_key(lua: &mlua::Lua, key: Arc<mlua::RegistryKey>) -> mlua::Result<()> {
+    match Arc::try_unwrap(key) {
+        Ok(key) => lua.remove_registry_value(key),
+        Err(_) => Ok(()),
+    }
+}
+
+/// Unregisters every Lua callback and timer, e.g. before the bot restarts.
+pub fn clear_callbacks(bot: &Bot) {
+    let lua = &bot.scripting.lua;
+    let callbacks: Vec<_> = bot.scripting.callbacks.lock().unwrap().drain().collect();
+    for (_, callbacks) in callbacks {
+        for cb in callbacks {
+            let _ = release_key(lua, cb.key);
+        }
+    }
+
+    let timers: Vec<_> = bot.scripting.timers.lock().unwrap().drain().collect();
+    for (_, timer) in timers {
+        let _ = release_key(lua, timer.key);
+    }
+}
+
+/// Drops every task started with `spawn`, e.g. when the bot disconnects.
+pub fn cancel_tasks(bot: &Bot) {
+    let lua = &bot.scripting.lua;
+    if let Ok(cancel) = lua.named_registry_value::<mlua::Function>(TASK_CANCEL) {
+        let _ = cancel.call::<()>(());
     }
 }
 
@@ -129,3 +722,53 @@ pub fn has_callbacks(bot: &Bot, event: &str) -> bool {
     let cbs = bot.scripting.callbacks.lock().unwrap();
     cbs.get(event).is_some_and(|v| !v.is_empty())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::status::PeerStatus;
+    use gtitem_r::structs::ItemDatabase;
+    use std::sync::RwLock;
+
+    fn test_bot() -> Arc<Bot> {
+        let item_database = Arc::new(RwLock::new(ItemDatabase::new()));
+        let bot = Bot::new(LoginVia::default(), None, item_database, None).0;
+        initialize(&bot);
+        bot
+    }
+
+    #[test]
+    fn test_blocked_warp_from_callback() {
+        let bot = test_bot();
+        bot.set_peer_status(PeerStatus::InGame);
+        bot.config.set_warp_allowlist(vec!["HOME".to_string()]);
+
+        let run = bot
+            .run_script(
+                r#"
+                local bot = getBot()
+                bot:on("onWarpBlocked", function(world) blocked = world end)
+                bot:on("onConsole", function() bot:warp("ELSEWHERE") end)
+                "#,
+            )
+            .unwrap();
+        assert_eq!(run.error, None);
+
+        invoke_callbacks(&bot, "onConsole", "hello".to_string());
+        let blocked: String = bot.scripting.lua.globals().get("blocked").unwrap();
+        assert_eq!(blocked, "ELSEWHERE");
+    }
+
+    #[test]
+    fn test_panicking_run_frees_the_slot() {
+        let bot = test_bot();
+        let panicked = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+            let _running = bot.scripting.begin_run().unwrap();
+            assert!(bot.scripting.begin_run().is_none());
+            panic!("overflow");
+        }));
+        assert!(panicked.is_err());
+        assert!(!bot.scripting.currently_executing.load(Ordering::SeqCst));
+        assert!(bot.run_script("x = 1").is_some());
+    }
+}
@@ -1,11 +1,38 @@
 use crate::astar::AStar;
 use crate::types::bot::State;
+use std::sync::atomic::{AtomicU32, Ordering};
 use std::sync::{Mutex, MutexGuard, RwLock};
+use std::time::Duration;
+
+/// Gravity the server reports for a world with unmodified physics.
+pub const NORMAL_GRAVITY: f32 = 1000.0;
+
+/// Scales a path step's delay for the world's gravity. Falling a tile takes
+/// time proportional to `1 / sqrt(gravity)`, so only vertical steps change.
+fn scaled_step_delay(base_ms: u32, gravity: f32, vertical: bool) -> Duration {
+    let factor = if vertical && gravity > 0.0 {
+        (NORMAL_GRAVITY / gravity).sqrt().clamp(0.5, 3.0)
+    } else {
+        1.0
+    };
+    Duration::from_millis((base_ms as f32 * factor).round() as u64)
+}
 
 pub struct MovementController {
     position: RwLock<(f32, f32)>,
     state: Mutex<State>,
     astar: Mutex<AStar>,
+    /// Explicit moves (`walk`, `find_path`) in progress; they can nest.
+    moves_in_progress: AtomicU32,
+}
+
+/// Marks an explicit move as in progress until dropped.
+pub struct MoveGuard<'a>(&'a AtomicU32);
+
+impl Drop for MoveGuard<'_> {
+    fn drop(&mut self) {
+        self.0.fetch_sub(1, Ordering::SeqCst);
+    }
 }
 
 impl MovementController {
@@ -14,6 +41,7 @@ impl MovementController {
             position: RwLock::new((0.0, 0.0)),
             state: Mutex::new(State::default()),
             astar: Mutex::new(AStar::new()),
+            moves_in_progress: AtomicU32::new(0),
         }
     }
 
@@ -44,6 +72,30 @@ impl MovementController {
     pub fn astar(&self) -> MutexGuard<'_, AStar> {
         self.astar.lock().unwrap()
     }
+
+    pub fn begin_move(&self) -> MoveGuard<'_> {
+        self.moves_in_progress.fetch_add(1, Ordering::SeqCst);
+        MoveGuard(&self.moves_in_progress)
+    }
+
+    pub fn is_moving(&self) -> bool {
+        self.moves_in_progress.load(Ordering::SeqCst) > 0
+    }
+
+    /// Gravity from the last `SetCharacterState`, or `NORMAL_GRAVITY` until
+    /// the server has sent one.
+    pub fn gravity(&self) -> f32 {
+        let gravity = self.state().gravity;
+        if gravity > 0.0 {
+            gravity
+        } else {
+            NORMAL_GRAVITY
+        }
+    }
+
+    pub fn step_delay(&self, base_ms: u32, vertical: bool) -> Duration {
+        scaled_step_delay(base_ms, self.gravity(), vertical)
+    }
 }
 
 impl Default for MovementController {
@@ -81,6 +133,33 @@ mod tests {
         assert_eq!(state.hack_type, 42);
     }
 
+    #[test]
+    fn test_move_guard() {
+        let movement = MovementController::new();
+        assert!(!movement.is_moving());
+        {
+            let _outer = movement.begin_move();
+            let inner = movement.begin_move();
+            drop(inner);
+            assert!(movement.is_moving());
+        }
+        assert!(!movement.is_moving());
+    }
+
+    #[test]
+    fn test_low_gravity_slows_vertical_steps() {
+        let movement = MovementController::new();
+        assert_eq!(movement.gravity(), NORMAL_GRAVITY);
+        assert_eq!(movement.step_delay(150, true), Duration::from_millis(150));
+
+        movement.state().gravity = 250.0;
+        assert_eq!(movement.step_delay(150, true), Duration::from_millis(300));
+        assert_eq!(movement.step_delay(150, false), Duration::from_millis(150));
+
+        movement.state().gravity = 1.0;
+        assert_eq!(movement.step_delay(100, true), Duration::from_millis(300));
+    }
+
     #[test]
     fn test_concurrent_position_updates() {
         let movement = Arc::new(MovementController::new());
@@ -1,8 +1,13 @@
 use crate::Socks5Config;
+use crate::proxy::ProxyCheck;
 use crate::socks5_udp::Socks5UdpSocket;
 use rusty_enet::{EventNoRef, HostSettings, Packet, PeerID};
 use std::net::{SocketAddr, UdpSocket};
 use std::sync::Mutex;
+use std::time::Duration;
+
+/// ENet's own keep-alive cadence, used unless the bot overrides it.
+pub const DEFAULT_PING_INTERVAL: Duration = Duration::from_millis(500);
 
 enum SessionHost {
     Direct(rusty_enet::Host<UdpSocket>),
@@ -12,61 +17,108 @@ enum SessionHost {
 pub struct NetworkSession {
     host: Mutex<SessionHost>,
     peer_id: Mutex<Option<PeerID>>,
+    local_addr: SocketAddr,
+    proxy: Mutex<Option<Socks5Config>>,
+    /// Proxy to switch to before the next connect; `Some(None)` goes direct.
+    queued_proxy: Mutex<Option<Option<Socks5Config>>>,
+    last_proxy_check: Mutex<Option<ProxyCheck>>,
+    /// Server to use for the next connect instead of the fetched server data.
+    queued_endpoint: Mutex<Option<SocketAddr>>,
+}
+
+fn build_host(
+    local_addr: SocketAddr,
+    socks5_config: Option<&Socks5Config>,
+) -> Result<SessionHost, String> {
+    let settings = || HostSettings {
+        peer_limit: 1,
+        channel_limit: 2,
+        compressor: Some(Box::new(rusty_enet::RangeCoder::new())),
+        checksum: Some(Box::new(rusty_enet::crc32)),
+        using_new_packet: true,
+        ..Default::default()
+    };
+
+    match socks5_config {
+        Some(cfg) => {
+            let socks5_socket = Socks5UdpSocket::bind_through_proxy(
+                local_addr,
+                cfg.proxy_addr,
+                cfg.username.as_deref(),
+                cfg.password.as_deref(),
+            )
+            .map_err(|e| format!("Failed to create SOCKS5 socket: {}", e))?;
+
+            let host = rusty_enet::Host::<Socks5UdpSocket>::new(socks5_socket, settings())
+                .map_err(|e| format!("Failed to create SOCKS5 host: {:?}", e))?;
+            Ok(SessionHost::Socks5(host))
+        }
+        None => {
+            let socket = UdpSocket::bind(local_addr)
+                .map_err(|e| format!("Failed to bind UDP socket: {}", e))?;
+
+            let host = rusty_enet::Host::<UdpSocket>::new(socket, settings())
+                .map_err(|e| format!("Failed to create direct host: {:?}", e))?;
+            Ok(SessionHost::Direct(host))
+        }
+    }
 }
 
 impl NetworkSession {
     pub fn new(local_addr: SocketAddr, socks5_config: Option<Socks5Config>) -> Self {
-        let host = match socks5_config {
-            Some(cfg) => {
-                let socks5_socket = Socks5UdpSocket::bind_through_proxy(
-                    local_addr,
-                    cfg.proxy_addr,
-                    cfg.username.as_deref(),
-                    cfg.password.as_deref(),
-                )
-                .expect("Failed to create SOCKS5 socket");
-
-                let host = rusty_enet::Host::<Socks5UdpSocket>::new(
-                    socks5_socket,
-                    HostSettings {
-                        peer_limit: 1,
-                        channel_limit: 2,
-                        compressor: Some(Box::new(rusty_enet::RangeCoder::new())),
-                        checksum: Some(Box::new(rusty_enet::crc32)),
-                        using_new_packet: true,
-                        ..Default::default()
-                    },
-                )
-                .expect("Failed to create SOCKS5 host");
-
-                SessionHost::Socks5(host)
-            }
-            None => {
-                let socket = UdpSocket::bind(local_addr).expect("Failed to bind UDP socket");
-
-                let host = rusty_enet::Host::<UdpSocket>::new(
-                    socket,
-                    HostSettings {
-                        peer_limit: 1,
-                        channel_limit: 2,
-                        compressor: Some(Box::new(rusty_enet::RangeCoder::new())),
-                        checksum: Some(Box::new(rusty_enet::crc32)),
-                        using_new_packet: true,
-                        ..Default::default()
-                    },
-                )
-                .expect("Failed to create direct host");
-
-                SessionHost::Direct(host)
-            }
-        };
+        let host =
+            build_host(local_addr, socks5_config.as_ref()).unwrap_or_else(|e| panic!("{}", e));
 
         Self {
             host: Mutex::new(host),
             peer_id: Mutex::new(None),
+            local_addr,
+            proxy: Mutex::new(socks5_config),
+            queued_proxy: Mutex::new(None),
+            last_proxy_check: Mutex::new(None),
+            queued_endpoint: Mutex::new(None),
         }
     }
 
+    pub fn proxy(&self) -> Option<Socks5Config> {
+        self.proxy.lock().unwrap().clone()
+    }
+
+    /// Switches proxies the next time [`NetworkSession::apply_queued_proxy`] runs.
+    pub fn queue_proxy(&self, socks5_config: Option<Socks5Config>) {
+        *self.queued_proxy.lock().unwrap() = Some(socks5_config);
+    }
+
+    /// Rebuilds the host on the queued proxy, if one is queued. Must only run
+    /// while disconnected. On failure the current host and proxy are kept.
+    pub fn apply_queued_proxy(&self) -> Option<Result<(), String>> {
+        let socks5_config = self.queued_proxy.lock().unwrap().take()?;
+        Some(
+            build_host(self.local_addr, socks5_config.as_ref()).map(|host| {
+                *self.host.lock().unwrap() = host;
+                *self.peer_id.lock().unwrap() = None;
+                *self.proxy.lock().unwrap() = socks5_config;
+                *self.last_proxy_check.lock().unwrap() = None;
+            }),
+        )
+    }
+
+    pub fn queue_endpoint(&self, address: SocketAddr) {
+        *self.queued_endpoint.lock().unwrap() = Some(address);
+    }
+
+    pub fn take_queued_endpoint(&self) -> Option<SocketAddr> {
+        self.queued_endpoint.lock().unwrap().take()
+    }
+
+    pub fn last_proxy_check(&self) -> Option<ProxyCheck> {
+        self.last_proxy_check.lock().unwrap().clone()
+    }
+
+    pub fn set_last_proxy_check(&self, check: ProxyCheck) {
+        *self.last_proxy_check.lock().unwrap() = Some(check);
+    }
+
     pub fn connect(&self, address: SocketAddr) {
         let mut host = self.host.lock().unwrap();
         match &mut *host {
@@ -147,6 +199,20 @@ impl NetworkSession {
         Some(ping as u32)
     }
 
+    /// Sets how often ENet pings the connected peer. Does nothing while
+    /// disconnected.
+    pub fn set_ping_interval(&self, interval: Duration) {
+        let Some(peer_id) = self.peer_id() else {
+            return;
+        };
+
+        let mut host = self.host.lock().unwrap();
+        match &mut *host {
+            SessionHost::Direct(host) => host.peer_mut(peer_id).set_ping_interval(interval),
+            SessionHost::Socks5(host) => host.peer_mut(peer_id).set_ping_interval(interval),
+        }
+    }
+
     pub fn service(&self) -> Option<EventNoRef> {
         let mut host = self.host.lock().unwrap();
         match &mut *host {
@@ -1,7 +1,10 @@
-use crate::events::{BotEvent, EventType};
+use crate::events::{BotEvent, EventType, LogLevel};
+use crate::items_sync;
 use crate::lua;
+use crate::types::error::MoriError;
 use crate::types::net_game_packet::{NetGamePacket, NetGamePacketData};
 use crate::types::net_message::NetMessage;
+use crate::types::warp::WarpResult;
 use crate::utils::proton::HashMode;
 use crate::{Bot, utils, variant_handler};
 use byteorder::{ByteOrder, LittleEndian};
@@ -76,14 +79,21 @@ pub fn handle(bot: &Arc<Bot>, data: &[u8]) {
 
             lua::invoke_callbacks(bot, "onTextPacket", (3u32, message.clone()));
 
+            bot.handle_throttle_message(&message);
+
             if message.contains("logon_fail") {
+                bot.handle_ban_message(&message);
                 bot.disconnect();
                 bot.sleep_with_timeout(15);
             }
         }
         NetMessage::GamePacket => {
-            let parsed = NetGamePacketData::from_bytes(&data[4..])
-                .expect("Failed to parse NetGamePacketData");
+            let Some(parsed) = NetGamePacketData::from_bytes(&data[4..]) else {
+                let error = MoriError::ParseFailed("game packet".to_string());
+                bot.runtime
+                    .push_log_level(LogLevel::Error, error.to_string());
+                return;
+            };
             println!("GamePacket: {:?}", parsed._type);
             match parsed._type {
                 NetGamePacket::State => {
@@ -92,6 +102,12 @@ pub fn handle(bot: &Arc<Bot>, data: &[u8]) {
                         player.position.0 = parsed.vector_x;
                         player.position.1 = parsed.vector_y;
                     }
+                    drop(players);
+
+                    let mut entities = bot.world.entities.lock().unwrap();
+                    if let Some(entity) = entities.get_mut(&parsed.net_id) {
+                        entity.position = (parsed.vector_x, parsed.vector_y);
+                    }
                 }
                 NetGamePacket::CallFunction => {
                     variant_handler::handle(bot, &data[60..]);
@@ -103,6 +119,9 @@ pub fn handle(bot: &Arc<Bot>, data: &[u8]) {
                     let item_database = item_database_lock.deref();
                     let mut world_lock = bot.world.data.lock().unwrap();
                     let _ = world_lock.parse(&data[60..], item_database);
+                    bot.world.set_checkpoint(None);
+                    bot.world.set_weather(0);
+                    bot.world.set_background(0);
 
                     // Emit WorldLoaded event
                     let world_name = world_lock.name.clone();
@@ -112,14 +131,6 @@ pub fn handle(bot: &Arc<Bot>, data: &[u8]) {
                         height: world_lock.height,
                     }));
 
-                    lua::invoke_callbacks(bot, "onWorldLoad", world_name);
-
-                    // Update peer status to InWorld
-                    {
-                        let mut peer_status = bot.peer_status.lock().unwrap();
-                        *peer_status = crate::types::status::PeerStatus::InWorld;
-                    }
-
                     if !world_lock.tiles.is_empty() {
                         let width = world_lock.width;
                         let height = world_lock.height;
@@ -150,9 +161,21 @@ pub fn handle(bot: &Arc<Bot>, data: &[u8]) {
                             &collision_data,
                         );
                     }
+
+                    // Waiters and callbacks may read the world they just
+                    // entered, so run them with the locks released.
+                    drop(world_lock);
+                    drop(item_database_lock);
+                    // Loading a world means a kick seen earlier didn't end the session.
+                    bot.runtime.clear_stale_kick();
+                    bot.finish_warp(WarpResult::Entered(world_name.clone()));
+
+                    lua::invoke_callbacks(bot, "onWorldLoad", world_name);
+
+                    bot.set_peer_status(crate::types::status::PeerStatus::InWorld);
                 }
                 NetGamePacket::SendInventoryState => {
-                    bot.inventory.parse(&data[60..]);
+                    bot.inventory.parse(&data[60..], bot.config.debug());
 
                     // Emit InventoryParsed event
                     let (size, item_count) = bot.inventory.size_and_count();
@@ -233,17 +256,36 @@ pub fn handle(bot: &Arc<Bot>, data: &[u8]) {
                     let mut decoder = ZlibDecoder::new(data);
                     let mut data = Vec::new();
                     decoder.read_to_end(&mut data).unwrap();
-                    fs::write("items.dat", &data).unwrap();
+                    // Other bots may be reading the shared file, so replace
+                    // it in one step rather than writing over it.
+                    const TEMP_PATH: &str = "items.dat.tmp";
+                    if let Err(e) =
+                        fs::write(TEMP_PATH, &data).and_then(|_| fs::rename(TEMP_PATH, "items.dat"))
+                    {
+                        bot.runtime.push_log_level(
+                            LogLevel::Error,
+                            format!("Failed to save items.dat: {}", e),
+                        );
+                    }
+                    items_sync::release();
 
+                    bot.runtime.expect_motd();
                     bot.send_text_packet(
                         NetMessage::GenericText,
                         b"action|enter_game\n",
                     );
                     bot.runtime.set_redirecting(false);
 
-                    let item_database =
-                        gtitem_r::load_from_file("items.dat").expect("Failed to load items.dat");
-                    *bot.world.item_database.write().unwrap() = item_database;
+                    match gtitem_r::load_from_file("items.dat") {
+                        Ok(item_database) => {
+                            *bot.world.item_database.write().unwrap() = item_database
+                        }
+                        Err(_) => bot.runtime.push_log_level(
+                            LogLevel::Error,
+                            MoriError::ParseFailed("items.dat".to_string()).to_string(),
+                        ),
+                    }
+                    bot.set_in_game();
                 }
                 NetGamePacket::TileChangeRequest => {
                     handle_tile_change_request(bot, &parsed);
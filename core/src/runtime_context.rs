@@ -1,16 +1,107 @@
+use crate::events::LogLevel;
+use crate::types::disconnect::DisconnectReason;
+use serde::Serialize;
+use std::collections::VecDeque;
+use std::fmt;
 use std::sync::{
     Mutex, RwLock, RwLockReadGuard,
-    atomic::{AtomicU32, Ordering},
+    atomic::{AtomicBool, AtomicU32, AtomicU64, Ordering},
 };
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct LogEntry {
+    /// Milliseconds since the Unix epoch.
+    pub timestamp: u64,
+    pub level: LogLevel,
+    pub message: String,
+}
+
+impl LogEntry {
+    pub fn new<S: Into<String>>(level: LogLevel, message: S) -> Self {
+        Self {
+            timestamp: now_millis(),
+            level,
+            message: message.into(),
+        }
+    }
+}
+
+fn now_millis() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or(0)
+}
+
+/// Text form used by the terminal view: `[HH:MM:SS] message`, with the level
+/// shown for anything other than info. Times are UTC.
+impl fmt::Display for LogEntry {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let seconds = (self.timestamp / 1000) % 86_400;
+        write!(
+            f,
+            "[{:02}:{:02}:{:02}] ",
+            seconds / 3600,
+            (seconds / 60) % 60,
+            seconds % 60
+        )?;
+        if self.level != LogLevel::Info {
+            write!(f, "{}: ", self.level)?;
+        }
+        write!(f, "{}", self.message)
+    }
+}
+
+/// Audit entries kept per bot; the oldest are dropped first.
+const AUDIT_CAPACITY: usize = 500;
+
+/// Who asked the bot to act.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ActionSource {
+    /// The host application calling `Bot` directly (GUI, API).
+    Api,
+    Lua,
+    /// Automation such as leave rules and routes.
+    Auto,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct AuditEntry {
+    /// Milliseconds since the Unix epoch.
+    pub timestamp: u64,
+    pub source: ActionSource,
+    pub action: String,
+}
+
+/// How often a `try_lock` path gave up because the lock was held, usually by
+/// the packet thread.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+pub struct LockContention {
+    pub world: u64,
+    pub inventory: u64,
+}
 
 #[derive(Debug)]
 pub struct RuntimeContext {
     net_id: Mutex<u32>,
     user_id: Mutex<u32>,
     ping: AtomicU32,
-    logs: RwLock<Vec<String>>,
+    throttle_seconds: AtomicU64,
+    disconnect_reason: Mutex<Option<DisconnectReason>>,
+    session_lost: AtomicBool,
+    parked: AtomicBool,
+    demand: AtomicBool,
+    last_action: Mutex<Instant>,
+    logs: RwLock<Vec<LogEntry>>,
+    motd: Mutex<Option<String>>,
+    awaiting_motd: AtomicBool,
     is_running: Mutex<bool>,
     is_redirecting: Mutex<bool>,
+    world_lock_misses: AtomicU64,
+    inventory_lock_misses: AtomicU64,
+    audit: Mutex<VecDeque<AuditEntry>>,
 }
 
 impl RuntimeContext {
@@ -19,9 +110,20 @@ impl RuntimeContext {
             net_id: Mutex::new(0),
             user_id: Mutex::new(0),
             ping: AtomicU32::new(0),
+            throttle_seconds: AtomicU64::new(0),
+            disconnect_reason: Mutex::new(None),
+            session_lost: AtomicBool::new(false),
+            parked: AtomicBool::new(false),
+            demand: AtomicBool::new(false),
+            last_action: Mutex::new(Instant::now()),
             logs: RwLock::new(Vec::new()),
+            motd: Mutex::new(None),
+            awaiting_motd: AtomicBool::new(false),
             is_running: Mutex::new(true),
             is_redirecting: Mutex::new(false),
+            world_lock_misses: AtomicU64::new(0),
+            inventory_lock_misses: AtomicU64::new(0),
+            audit: Mutex::new(VecDeque::new()),
         }
     }
 
@@ -51,6 +153,98 @@ impl RuntimeContext {
         self.ping.store(value, Ordering::Relaxed);
     }
 
+    /// Records a server throttle; the reconnect loop waits this long before logging in again.
+    pub fn set_throttle(&self, seconds: u64) {
+        self.throttle_seconds.store(seconds, Ordering::Relaxed);
+    }
+
+    pub fn take_throttle(&self) -> u64 {
+        self.throttle_seconds.swap(0, Ordering::Relaxed)
+    }
+
+    /// Notes why the current session is ending. The first reason recorded
+    /// wins, so a kick isn't overwritten by the disconnect that follows it.
+    pub fn set_disconnect_reason(&self, reason: DisconnectReason) {
+        self.disconnect_reason.lock().unwrap().get_or_insert(reason);
+    }
+
+    /// Forgets any reason left over from an earlier session.
+    pub fn clear_disconnect_reason(&self) {
+        *self.disconnect_reason.lock().unwrap() = None;
+    }
+
+    /// Drops a kick recorded in a session that carried on anyway, such as a
+    /// kick from a world, along with its reconnect cooldown.
+    pub fn clear_stale_kick(&self) {
+        let mut reason = self.disconnect_reason.lock().unwrap();
+        if *reason == Some(DisconnectReason::Kicked) {
+            *reason = None;
+            self.throttle_seconds.store(0, Ordering::Relaxed);
+        }
+    }
+
+    pub fn take_disconnect_reason(&self) -> Option<DisconnectReason> {
+        self.disconnect_reason.lock().unwrap().take()
+    }
+
+    /// Notes that an in-game session dropped, so the next time the bot gets
+    /// back in game counts as a reconnect.
+    pub fn mark_session_lost(&self) {
+        self.session_lost.store(true, Ordering::SeqCst);
+    }
+
+    pub fn take_session_lost(&self) -> bool {
+        self.session_lost.swap(false, Ordering::SeqCst)
+    }
+
+    /// Set while the bot stays offline after an idle disconnect.
+    pub fn set_parked(&self, parked: bool) {
+        self.parked.store(parked, Ordering::SeqCst);
+    }
+
+    pub fn is_parked(&self) -> bool {
+        self.parked.load(Ordering::SeqCst)
+    }
+
+    /// Asks a parked bot to reconnect because something wants to act.
+    pub fn request_demand(&self) {
+        self.demand.store(true, Ordering::SeqCst);
+    }
+
+    pub fn take_demand(&self) -> bool {
+        self.demand.swap(false, Ordering::SeqCst)
+    }
+
+    /// Called whenever the bot sends an action to the server.
+    pub fn mark_action(&self) {
+        *self.last_action.lock().unwrap() = Instant::now();
+    }
+
+    /// Time since the last action, or since startup if none was sent yet.
+    pub fn idle_time(&self) -> Duration {
+        self.last_action.lock().unwrap().elapsed()
+    }
+
+    /// Greeting the server sent after the most recent login, if any.
+    pub fn motd(&self) -> Option<String> {
+        self.motd.lock().unwrap().clone()
+    }
+
+    /// Called when entering the game; the next console message becomes the MOTD.
+    pub fn expect_motd(&self) {
+        *self.motd.lock().unwrap() = None;
+        self.awaiting_motd.store(true, Ordering::Relaxed);
+    }
+
+    /// Stores `message` as the MOTD if one is expected. Returns whether it was taken.
+    pub fn capture_motd(&self, message: &str) -> bool {
+        if !self.awaiting_motd.swap(false, Ordering::Relaxed) {
+            return false;
+        }
+        *self.motd.lock().unwrap() = Some(message.to_string());
+        true
+    }
+
     pub fn is_running(&self) -> bool {
         *self.is_running.lock().unwrap()
     }
@@ -69,9 +263,44 @@ impl RuntimeContext {
         *state = redirecting;
     }
 
+    pub fn record_world_lock_miss(&self) {
+        self.world_lock_misses.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn record_inventory_lock_miss(&self) {
+        self.inventory_lock_misses.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn lock_contention(&self) -> LockContention {
+        LockContention {
+            world: self.world_lock_misses.load(Ordering::Relaxed),
+            inventory: self.inventory_lock_misses.load(Ordering::Relaxed),
+        }
+    }
+
+    pub fn push_audit(&self, source: ActionSource, action: String) {
+        let mut audit = self.audit.lock().unwrap();
+        if audit.len() == AUDIT_CAPACITY {
+            audit.pop_front();
+        }
+        audit.push_back(AuditEntry {
+            timestamp: now_millis(),
+            source,
+            action,
+        });
+    }
+
+    pub fn audit_snapshot(&self) -> Vec<AuditEntry> {
+        self.audit.lock().unwrap().iter().cloned().collect()
+    }
+
     pub fn push_log<S: Into<String>>(&self, message: S) {
+        self.push_log_level(LogLevel::Info, message);
+    }
+
+    pub fn push_log_level<S: Into<String>>(&self, level: LogLevel, message: S) {
         let mut logs = self.logs.write().unwrap();
-        logs.push(message.into());
+        logs.push(LogEntry::new(level, message));
     }
 
     pub fn clear_logs(&self) {
@@ -79,13 +308,23 @@ impl RuntimeContext {
         logs.clear();
     }
 
-    pub fn logs(&self) -> RwLockReadGuard<'_, Vec<String>> {
+    pub fn logs(&self) -> RwLockReadGuard<'_, Vec<LogEntry>> {
         self.logs.read().unwrap()
     }
 
-    pub fn logs_snapshot(&self) -> Vec<String> {
+    pub fn logs_snapshot(&self) -> Vec<LogEntry> {
         self.logs.read().unwrap().clone()
     }
+
+    /// Logs as formatted text lines, for consumers that predate [`LogEntry`].
+    pub fn logs_text(&self) -> Vec<String> {
+        self.logs
+            .read()
+            .unwrap()
+            .iter()
+            .map(|entry| entry.to_string())
+            .collect()
+    }
 }
 
 impl Default for RuntimeContext {
@@ -108,6 +347,40 @@ mod tests {
         assert!(runtime.is_running());
         assert!(!runtime.is_redirecting());
         assert!(runtime.logs().is_empty());
+        assert_eq!(runtime.motd(), None);
+    }
+
+    #[test]
+    fn test_session_lost() {
+        let runtime = RuntimeContext::new();
+        assert!(!runtime.take_session_lost());
+
+        runtime.mark_session_lost();
+        assert!(runtime.take_session_lost());
+        assert!(!runtime.take_session_lost());
+    }
+
+    #[test]
+    fn test_demand_wakes_once() {
+        let runtime = RuntimeContext::new();
+        assert!(!runtime.is_parked());
+        assert!(!runtime.take_demand());
+
+        runtime.set_parked(true);
+        runtime.request_demand();
+        assert!(runtime.is_parked());
+        assert!(runtime.take_demand());
+        assert!(!runtime.take_demand());
+    }
+
+    #[test]
+    fn test_idle_time_resets_on_action() {
+        let runtime = RuntimeContext::new();
+        thread::sleep(Duration::from_millis(20));
+        assert!(runtime.idle_time() >= Duration::from_millis(20));
+
+        runtime.mark_action();
+        assert!(runtime.idle_time() < Duration::from_millis(20));
     }
 
     #[test]
@@ -123,7 +396,94 @@ mod tests {
         assert_eq!(runtime.ping(), 123);
         assert!(!runtime.is_running());
         assert!(runtime.is_redirecting());
-        assert_eq!(runtime.logs_snapshot(), vec!["hello".to_string()]);
+        let logs = runtime.logs_snapshot();
+        assert_eq!(logs.len(), 1);
+        assert_eq!(logs[0].message, "hello");
+        assert_eq!(logs[0].level, LogLevel::Info);
+    }
+
+    #[test]
+    fn test_log_entry_text() {
+        let entry = LogEntry {
+            timestamp: ((13 * 3600 + 5 * 60 + 9) * 1000) as u64,
+            level: LogLevel::Info,
+            message: "hello".to_string(),
+        };
+        assert_eq!(entry.to_string(), "[13:05:09] hello");
+
+        let entry = LogEntry {
+            level: LogLevel::Warning,
+            ..entry
+        };
+        assert_eq!(entry.to_string(), "[13:05:09] WARNING: hello");
+    }
+
+    #[test]
+    fn test_motd() {
+        let runtime = RuntimeContext::new();
+        assert!(!runtime.capture_motd("before login"));
+
+        runtime.expect_motd();
+        assert!(runtime.capture_motd("Welcome back!"));
+        assert!(!runtime.capture_motd("later message"));
+        assert_eq!(runtime.motd().as_deref(), Some("Welcome back!"));
+    }
+
+    #[test]
+    fn test_throttle() {
+        let runtime = RuntimeContext::new();
+        assert_eq!(runtime.take_throttle(), 0);
+
+        runtime.set_throttle(45);
+        assert_eq!(runtime.take_throttle(), 45);
+        assert_eq!(runtime.take_throttle(), 0);
+    }
+
+    #[test]
+    fn test_stale_kick_is_cleared() {
+        let runtime = RuntimeContext::new();
+        runtime.set_disconnect_reason(DisconnectReason::Kicked);
+        runtime.set_throttle(30);
+        runtime.clear_stale_kick();
+        assert_eq!(runtime.take_disconnect_reason(), None);
+        assert_eq!(runtime.take_throttle(), 0);
+
+        runtime.set_disconnect_reason(DisconnectReason::Idle);
+        runtime.clear_stale_kick();
+        assert_eq!(
+            runtime.take_disconnect_reason(),
+            Some(DisconnectReason::Idle)
+        );
+    }
+
+    #[test]
+    fn test_lock_contention() {
+        let runtime = RuntimeContext::new();
+        assert_eq!(runtime.lock_contention(), LockContention::default());
+
+        runtime.record_world_lock_miss();
+        runtime.record_world_lock_miss();
+        runtime.record_inventory_lock_miss();
+        assert_eq!(
+            runtime.lock_contention(),
+            LockContention {
+                world: 2,
+                inventory: 1
+            }
+        );
+    }
+
+    #[test]
+    fn test_audit_is_bounded() {
+        let runtime = RuntimeContext::new();
+        for i in 0..AUDIT_CAPACITY + 5 {
+            runtime.push_audit(ActionSource::Lua, format!("say {}", i));
+        }
+
+        let audit = runtime.audit_snapshot();
+        assert_eq!(audit.len(), AUDIT_CAPACITY);
+        assert_eq!(audit[0].action, "say 5");
+        assert_eq!(audit[0].source, ActionSource::Lua);
     }
 
     #[test]
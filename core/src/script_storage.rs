@@ -0,0 +1,239 @@
+use serde_json::{Map, Value};
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// Directory holding one `<bot>.json` file per bot.
+pub const STORAGE_DIR: &str = "storage";
+
+/// Key-value store behind the Lua `storage` table, written through to disk on
+/// every change.
+#[derive(Debug)]
+pub struct ScriptStorage {
+    path: PathBuf,
+    values: Map<String, Value>,
+}
+
+impl ScriptStorage {
+    /// Storage file for the bot identified by `bot_key`, with anything that
+    /// isn't safe in a file name replaced.
+    pub fn path_for(bot_key: &str) -> PathBuf {
+        let name: String = bot_key
+            .chars()
+            .map(|c| {
+                if c.is_ascii_alphanumeric() || c == '_' || c == '-' {
+                    c
+                } else {
+                    '_'
+                }
+            })
+            .collect();
+        Path::new(STORAGE_DIR).join(format!("{}.json", name))
+    }
+
+    /// Opens the store at `path`; a missing file starts empty.
+    pub fn load<P: Into<PathBuf>>(path: P) -> io::Result<Self> {
+        let path = path.into();
+        let values = match fs::read_to_string(&path) {
+            Ok(text) => serde_json::from_str(&text)
+                .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Map::new(),
+            Err(e) => return Err(e),
+        };
+        Ok(Self { path, values })
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Moves the store to `path`, merging it into whatever is stored there.
+    /// Values already in memory win, since they were written more recently.
+    /// The old file is removed once the new one is saved.
+    pub fn move_to<P: Into<PathBuf>>(&mut self, path: P) -> io::Result<()> {
+        let mut target = ScriptStorage::load(path)?;
+        target.values.extend(std::mem::take(&mut self.values));
+        target.save()?;
+
+        let old = std::mem::replace(&mut self.path, target.path);
+        self.values = target.values;
+        match fs::remove_file(old) {
+            Err(e) if e.kind() != io::ErrorKind::NotFound => Err(e),
+            _ => Ok(()),
+        }
+    }
+
+    pub fn get(&self, key: &str) -> Option<&Value> {
+        self.values.get(key)
+    }
+
+    pub fn set(&mut self, key: String, value: Value) -> io::Result<()> {
+        self.values.insert(key, value);
+        self.save()
+    }
+
+    pub fn delete(&mut self, key: &str) -> io::Result<()> {
+        if self.values.remove(key).is_some() {
+            self.save()?;
+        }
+        Ok(())
+    }
+
+    /// Writes to a temporary file and renames it so a crash mid-write keeps
+    /// the previous contents.
+    fn save(&self) -> io::Result<()> {
+        if let Some(parent) = self.path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let tmp = self.path.with_extension("json.tmp");
+        fs::write(&tmp, serde_json::to_vec_pretty(&self.values)?)?;
+        fs::rename(tmp, &self.path)
+    }
+}
+
+/// Converts a storable Lua value: strings, numbers, booleans, or a table of those.
+pub fn lua_to_json(value: &mlua::Value) -> mlua::Result<Value> {
+    fn scalar(value: &mlua::Value) -> mlua::Result<Value> {
+        match value {
+            mlua::Value::Boolean(b) => Ok(Value::Bool(*b)),
+            mlua::Value::Integer(i) => Ok(Value::from(*i)),
+            mlua::Value::Number(n) => serde_json::Number::from_f64(*n)
+                .map(Value::Number)
+                .ok_or_else(|| mlua::Error::RuntimeError("cannot store NaN or inf".to_string())),
+            mlua::Value::String(s) => Ok(Value::String(s.to_str()?.to_string())),
+            other => Err(mlua::Error::RuntimeError(format!(
+                "cannot store a {} in storage",
+                other.type_name()
+            ))),
+        }
+    }
+
+    let mlua::Value::Table(table) = value else {
+        return scalar(value);
+    };
+
+    let len = table.raw_len();
+    let mut map = Map::new();
+    for pair in table.pairs::<mlua::Value, mlua::Value>() {
+        let (key, value) = pair?;
+        let key = match key {
+            mlua::Value::String(s) => s.to_str()?.to_string(),
+            mlua::Value::Integer(i) => i.to_string(),
+            other => {
+                return Err(mlua::Error::RuntimeError(format!(
+                    "cannot store a table with {} keys",
+                    other.type_name()
+                )));
+            }
+        };
+        map.insert(key, scalar(&value)?);
+    }
+
+    // Sequences round-trip as JSON arrays.
+    if len > 0 && map.len() == len {
+        let items: Option<Vec<Value>> = (1..=len).map(|i| map.remove(&i.to_string())).collect();
+        if let Some(items) = items {
+            return Ok(Value::Array(items));
+        }
+    }
+    Ok(Value::Object(map))
+}
+
+pub fn json_to_lua(lua: &mlua::Lua, value: &Value) -> mlua::Result<mlua::Value> {
+    Ok(match value {
+        Value::Null => mlua::Value::Nil,
+        Value::Bool(b) => mlua::Value::Boolean(*b),
+        Value::Number(n) => match n.as_i64() {
+            Some(i) => mlua::Value::Integer(i),
+            None => mlua::Value::Number(n.as_f64().unwrap_or(0.0)),
+        },
+        Value::String(s) => mlua::Value::String(lua.create_string(s)?),
+        Value::Array(items) => {
+            let table = lua.create_table()?;
+            for (i, item) in items.iter().enumerate() {
+                table.set(i + 1, json_to_lua(lua, item)?)?;
+            }
+            mlua::Value::Table(table)
+        }
+        Value::Object(map) => {
+            let table = lua.create_table()?;
+            for (key, item) in map {
+                table.set(key.as_str(), json_to_lua(lua, item)?)?;
+            }
+            mlua::Value::Table(table)
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_persists_across_loads() {
+        let path = std::env::temp_dir().join(format!("mori-storage-{}.json", std::process::id()));
+        let _ = fs::remove_file(&path);
+
+        let mut storage = ScriptStorage::load(&path).unwrap();
+        assert!(storage.get("runs").is_none());
+        storage.set("runs".to_string(), Value::from(3)).unwrap();
+        storage
+            .set("world".to_string(), Value::from("FARM"))
+            .unwrap();
+        storage.delete("world").unwrap();
+
+        let reloaded = ScriptStorage::load(&path).unwrap();
+        assert_eq!(reloaded.get("runs"), Some(&Value::from(3)));
+        assert!(reloaded.get("world").is_none());
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_move_to_merges() {
+        let dir = std::env::temp_dir();
+        let from = dir.join(format!("mori-storage-uuid-{}.json", std::process::id()));
+        let to = dir.join(format!("mori-storage-growid-{}.json", std::process::id()));
+        let _ = fs::remove_file(&from);
+        let _ = fs::remove_file(&to);
+
+        let mut existing = ScriptStorage::load(&to).unwrap();
+        existing.set("runs".to_string(), Value::from(9)).unwrap();
+        existing
+            .set("home".to_string(), Value::from("BASE"))
+            .unwrap();
+
+        let mut storage = ScriptStorage::load(&from).unwrap();
+        storage.set("runs".to_string(), Value::from(10)).unwrap();
+        storage.move_to(&to).unwrap();
+
+        assert_eq!(storage.path(), to.as_path());
+        assert!(!from.exists());
+        let reloaded = ScriptStorage::load(&to).unwrap();
+        assert_eq!(reloaded.get("runs"), Some(&Value::from(10)));
+        assert_eq!(reloaded.get("home"), Some(&Value::from("BASE")));
+        fs::remove_file(&to).unwrap();
+    }
+
+    #[test]
+    fn test_lua_roundtrip() {
+        let lua = mlua::Lua::new();
+        let list: mlua::Value = lua.load("return { 'a', 'b' }").eval().unwrap();
+        assert_eq!(lua_to_json(&list).unwrap(), serde_json::json!(["a", "b"]));
+
+        let map: mlua::Value = lua
+            .load("return { count = 2, done = true }")
+            .eval()
+            .unwrap();
+        let json = lua_to_json(&map).unwrap();
+        assert_eq!(json, serde_json::json!({ "count": 2, "done": true }));
+
+        let back = json_to_lua(&lua, &json).unwrap();
+        let mlua::Value::Table(table) = back else {
+            panic!("expected a table");
+        };
+        assert_eq!(table.get::<i64>("count").unwrap(), 2);
+
+        let nested: mlua::Value = lua.load("return { inner = {} }").eval().unwrap();
+        assert!(lua_to_json(&nested).is_err());
+    }
+}
@@ -0,0 +1,128 @@
+use std::cell::Cell;
+use std::sync::Mutex;
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// Process-wide governor shared by every bot's `send_game_packet`.
+static GOVERNOR: SendGovernor = SendGovernor::new();
+
+thread_local! {
+    /// Set on the network threads, whose automatic replies must never sleep.
+    static EXEMPT: Cell<bool> = const { Cell::new(false) };
+}
+
+/// Sets the ceiling on outgoing game packets per second across all bots.
+/// `None` (the default) disables pacing. Replies the network thread sends
+/// while handling the server aren't paced.
+pub fn set_global_send_rate(packets_per_second: Option<u32>) {
+    GOVERNOR.set_rate(packets_per_second);
+}
+
+pub fn global_send_rate() -> Option<u32> {
+    GOVERNOR.rate()
+}
+
+/// Waits for a send slot, unless the calling thread is exempt.
+pub(crate) fn acquire() {
+    if !EXEMPT.with(Cell::get) {
+        GOVERNOR.acquire();
+    }
+}
+
+/// Exempts the calling thread from pacing. Only scripts and API calls are
+/// slowed; packets sent while handling the server's stream go out at once.
+pub(crate) fn exempt_current_thread() {
+    EXEMPT.with(|exempt| exempt.set(true));
+}
+
+#[derive(Debug)]
+struct GovernorState {
+    packets_per_second: Option<u32>,
+    next_slot: Option<Instant>,
+}
+
+/// Hands out evenly spaced send slots; callers over the ceiling sleep until theirs.
+#[derive(Debug)]
+pub struct SendGovernor {
+    state: Mutex<GovernorState>,
+}
+
+impl SendGovernor {
+    pub const fn new() -> Self {
+        Self {
+            state: Mutex::new(GovernorState {
+                packets_per_second: None,
+                next_slot: None,
+            }),
+        }
+    }
+
+    pub fn set_rate(&self, packets_per_second: Option<u32>) {
+        let mut state = self.state.lock().unwrap();
+        state.packets_per_second = packets_per_second.filter(|&pps| pps > 0);
+        state.next_slot = None;
+    }
+
+    pub fn rate(&self) -> Option<u32> {
+        self.state.lock().unwrap().packets_per_second
+    }
+
+    /// Reserves the next slot and returns how long the caller must wait for it.
+    fn reserve(&self, now: Instant) -> Duration {
+        let mut state = self.state.lock().unwrap();
+        let Some(pps) = state.packets_per_second else {
+            return Duration::ZERO;
+        };
+
+        let interval = Duration::from_secs(1) / pps;
+        let slot = state.next_slot.map_or(now, |next| next.max(now));
+        state.next_slot = Some(slot + interval);
+        slot - now
+    }
+
+    pub fn acquire(&self) {
+        let wait = self.reserve(Instant::now());
+        if !wait.is_zero() {
+            thread::sleep(wait);
+        }
+    }
+}
+
+impl Default for SendGovernor {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_unlimited_by_default() {
+        let governor = SendGovernor::new();
+        let now = Instant::now();
+        assert_eq!(governor.rate(), None);
+        for _ in 0..100 {
+            assert_eq!(governor.reserve(now), Duration::ZERO);
+        }
+    }
+
+    #[test]
+    fn test_paces_excess_packets() {
+        let governor = SendGovernor::new();
+        governor.set_rate(Some(10));
+        let now = Instant::now();
+
+        assert_eq!(governor.reserve(now), Duration::ZERO);
+        assert_eq!(governor.reserve(now), Duration::from_millis(100));
+        assert_eq!(governor.reserve(now), Duration::from_millis(200));
+
+        // Idle time doesn't bank extra slots.
+        let later = now + Duration::from_secs(5);
+        assert_eq!(governor.reserve(later), Duration::ZERO);
+
+        governor.set_rate(Some(0));
+        assert_eq!(governor.rate(), None);
+    }
+}
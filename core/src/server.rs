@@ -3,6 +3,7 @@ use crate::types::server_data::ServerData;
 use anyhow::Result;
 use scraper::{Html, Selector};
 use serde_json::Value;
+use std::time::Duration;
 use ureq::config::Config;
 use urlencoding::encode;
 
@@ -92,6 +93,67 @@ pub fn get_server_data_with_proxy(
     ServerData::parse_from_response(&body)
 }
 
+/// Fetches a raw items.dat, e.g. from a private server's CDN.
+pub fn download_items_dat(url: &str, proxy: Option<&str>) -> Result<Vec<u8>> {
+    const MAX_ITEMS_DAT_SIZE: u64 = 64 * 1024 * 1024;
+
+    let agent = if let Some(proxy_url) = proxy {
+        let proxy = ureq::Proxy::new(proxy_url)?;
+        ureq::Agent::new_with_config(Config::builder().proxy(Some(proxy)).build())
+    } else {
+        ureq::Agent::new_with_defaults()
+    };
+
+    let data = agent
+        .get(url)
+        .call()?
+        .body_mut()
+        .with_config()
+        .limit(MAX_ITEMS_DAT_SIZE)
+        .read_to_vec()?;
+
+    Ok(data)
+}
+
+/// Performs a blocking GET (no `body`) or POST for scripts. Non-2xx statuses
+/// are returned rather than treated as errors.
+pub fn http_request(
+    url: &str,
+    body: Option<&str>,
+    headers: &[(String, String)],
+    proxy: Option<&str>,
+    timeout: Duration,
+) -> Result<(u16, String)> {
+    let mut config = Config::builder()
+        .timeout_global(Some(timeout))
+        .http_status_as_error(false);
+    if let Some(proxy_url) = proxy {
+        config = config.proxy(Some(ureq::Proxy::new(proxy_url)?));
+    }
+    let agent = ureq::Agent::new_with_config(config.build());
+
+    let mut response = match body {
+        Some(body) => {
+            let mut request = agent.post(url);
+            for (name, value) in headers {
+                request = request.header(name, value);
+            }
+            request.send(body)?
+        }
+        None => {
+            let mut request = agent.get(url);
+            for (name, value) in headers {
+                request = request.header(name, value);
+            }
+            request.call()?
+        }
+    };
+
+    let status = response.status().as_u16();
+    let body = response.body_mut().read_to_string()?;
+    Ok((status, body))
+}
+
 pub fn get_dashboard(login_url: &str, login_info: &LoginInfo) -> Result<DashboardLinks> {
     get_dashboard_with_proxy(login_url, login_info, None)
 }
@@ -0,0 +1,52 @@
+use byteorder::{LittleEndian, ReadBytesExt, WriteBytesExt};
+use std::fs::{File, OpenOptions};
+use std::io::{self, BufReader, Write};
+use std::path::Path;
+
+/// Appends a received packet to a session file as a `u32` little-endian length
+/// followed by the raw packet bytes.
+pub fn append<P: AsRef<Path>>(path: P, data: &[u8]) -> io::Result<()> {
+    let mut file = OpenOptions::new().create(true).append(true).open(path)?;
+    let mut buffer = Vec::with_capacity(4 + data.len());
+    buffer.write_u32::<LittleEndian>(data.len() as u32)?;
+    buffer.extend_from_slice(data);
+    file.write_all(&buffer)
+}
+
+/// Reads every packet from a session file written by [`append`].
+pub fn read_session<P: AsRef<Path>>(path: P) -> io::Result<Vec<Vec<u8>>> {
+    let mut reader = BufReader::new(File::open(path)?);
+    let mut packets = Vec::new();
+
+    loop {
+        let len = match reader.read_u32::<LittleEndian>() {
+            Ok(len) => len as usize,
+            Err(e) if e.kind() == io::ErrorKind::UnexpectedEof => break,
+            Err(e) => return Err(e),
+        };
+        let mut packet = vec![0; len];
+        io::Read::read_exact(&mut reader, &mut packet)?;
+        packets.push(packet);
+    }
+
+    Ok(packets)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_roundtrip() {
+        let path = std::env::temp_dir().join(format!("mori_session_{}.bin", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+
+        append(&path, &[4, 0, 0, 0, 1, 2, 3]).unwrap();
+        append(&path, &[3, 0, 0, 0]).unwrap();
+
+        let packets = read_session(&path).unwrap();
+        assert_eq!(packets, vec![vec![4, 0, 0, 0, 1, 2, 3], vec![3, 0, 0, 0]]);
+
+        let _ = std::fs::remove_file(&path);
+    }
+}
@@ -1,10 +1,23 @@
+use crate::ActionSource;
+use crate::Bot;
+use crate::game_world;
+use crate::script_storage::ScriptStorage;
+use crate::types::disconnect::{DisconnectReason, ReconnectAction};
+use crate::types::farm::FarmConfig;
 use crate::types::net_game_packet::{NetGamePacket, NetGamePacketData};
 use crate::types::net_message::NetMessage;
+use crate::types::player::{Friend, Player};
 use crate::types::status::PeerStatus;
-use crate::Bot;
+use crate::types::trade::Trade;
+use crate::types::warp::WarpResult;
+use crate::types::world_menu::WorldMenu;
+use crate::utils::dialog;
+use crate::utils::variant::VariantList;
+use crate::variant_handler::variant_list_to_lua_table;
+use gtitem_r::structs::ItemDatabase;
 use mlua::{Lua, UserData, UserDataFields, UserDataMethods};
-use std::collections::HashMap;
-use std::sync::atomic::AtomicBool;
+use std::collections::{HashMap, VecDeque};
+use std::sync::atomic::{AtomicBool, AtomicU32, Ordering};
 use std::sync::{Arc, Mutex};
 
 // ── Core bot types ──────────────────────────────────────────────
@@ -16,6 +29,8 @@ pub struct State {
     pub punch_length: u8,
     pub velocity: f32,
     pub gravity: f32,
+    /// Item selected with `hold_item` or last worn; 0 when nothing is held.
+    pub held_item: u32,
 }
 
 #[derive(Debug, Clone, Copy)]
@@ -23,6 +38,7 @@ pub struct DelayConfig {
     pub findpath_delay: u32,
     pub punch_delay: u32,
     pub place_delay: u32,
+    pub world_join_timeout: u32,
 }
 
 impl Default for DelayConfig {
@@ -31,6 +47,61 @@ impl Default for DelayConfig {
             findpath_delay: 150,
             punch_delay: 100,
             place_delay: 100,
+            world_join_timeout: 10_000,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum CollectStrategy {
+    /// Nearest item first.
+    #[default]
+    Greedy,
+    /// Row by row, alternating direction, so the bot doesn't zig-zag.
+    Sweep,
+    /// Ignore the pickup radius, for servers that allow remote collection.
+    Teleport,
+}
+
+impl CollectStrategy {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.to_lowercase().as_str() {
+            "greedy" => Some(CollectStrategy::Greedy),
+            "sweep" => Some(CollectStrategy::Sweep),
+            "teleport" => Some(CollectStrategy::Teleport),
+            _ => None,
+        }
+    }
+
+    /// Whether an item `distance_squared` pixels away may be collected.
+    /// `radius` in tiles overrides the strategy's own range.
+    pub fn in_range(&self, radius: Option<u32>, distance_squared: f32) -> bool {
+        match radius {
+            Some(tiles) => distance_squared <= (tiles as f32 * 32.0).powi(2),
+            // 3 tile radius (32*3)^2 = 96^2, unless the server lets us grab from anywhere
+            None => *self == CollectStrategy::Teleport || distance_squared <= 9216.0,
+        }
+    }
+
+    /// Orders `(item, distance_squared)` pairs in the sequence they should be collected.
+    pub fn order(&self, items: &mut [(gtworld_r::DroppedItem, f32)]) {
+        match self {
+            CollectStrategy::Greedy | CollectStrategy::Teleport => {
+                items.sort_by(|a, b| a.1.partial_cmp(&b.1).unwrap_or(std::cmp::Ordering::Equal));
+            }
+            CollectStrategy::Sweep => {
+                items.sort_by(|a, b| {
+                    let row_a = (a.0.y / 32.0).floor() as i32;
+                    let row_b = (b.0.y / 32.0).floor() as i32;
+                    row_a.cmp(&row_b).then_with(|| {
+                        let by_x =
+                            a.0.x
+                                .partial_cmp(&b.0.x)
+                                .unwrap_or(std::cmp::Ordering::Equal);
+                        if row_a % 2 == 0 { by_x } else { by_x.reverse() }
+                    })
+                });
+            }
         }
     }
 }
@@ -38,14 +109,72 @@ impl Default for DelayConfig {
 #[derive(Debug, Clone, Copy)]
 pub struct Automation {
     pub auto_collect: bool,
+    /// Skip auto-collect while a `walk`/`find_path` is moving the bot.
+    pub pause_collect_while_moving: bool,
     pub auto_reconnect: bool,
+    pub collect_strategy: CollectStrategy,
+    /// Collect radius in tiles; `None` keeps the 3-tile pickup range, or no
+    /// limit with [`CollectStrategy::Teleport`].
+    pub collect_radius: Option<u32>,
 }
 
 impl Default for Automation {
     fn default() -> Self {
         Self {
             auto_collect: true,
+            pause_collect_while_moving: true,
             auto_reconnect: true,
+            collect_strategy: CollectStrategy::default(),
+            collect_radius: None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub enum LeaveAction {
+    #[default]
+    Leave,
+    WarpTo(String),
+    Disconnect,
+    Ignore,
+}
+
+impl LeaveAction {
+    pub fn from_name(name: &str, world: Option<String>) -> Option<Self> {
+        match name.to_lowercase().as_str() {
+            "leave" => Some(LeaveAction::Leave),
+            "warp" => world.map(LeaveAction::WarpTo),
+            "disconnect" => Some(LeaveAction::Disconnect),
+            "ignore" | "none" => Some(LeaveAction::Ignore),
+            _ => None,
+        }
+    }
+}
+
+/// What the bot does when a moderator or invisible player spawns in its world.
+#[derive(Debug, Clone, Default)]
+pub struct LeaveRules {
+    pub on_mod: LeaveAction,
+    pub on_invisible: LeaveAction,
+}
+
+/// Console phrases treated as a kick, plus how long to wait before reconnecting.
+#[derive(Debug, Clone)]
+pub struct KickRules {
+    pub patterns: Vec<String>,
+    pub cooldown_secs: u64,
+}
+
+impl Default for KickRules {
+    fn default() -> Self {
+        Self {
+            patterns: vec![
+                "you have been kicked".to_string(),
+                "you were kicked".to_string(),
+                "has kicked you".to_string(),
+                "kicked from the world".to_string(),
+            ],
+            cooldown_secs: 0,
         }
     }
 }
@@ -64,25 +193,78 @@ impl Default for LoginVia {
     }
 }
 
+/// An action deferred until the bot has entered the game.
+pub type EarlyAction = Box<dyn FnOnce(&Bot) + Send>;
+
 #[derive(Default)]
 pub struct TemporaryData {
     pub drop: Mutex<(u32, u32)>,
     pub trash: Mutex<(u32, u32)>,
     pub dialog_callback: Mutex<Option<fn(&Bot)>>,
+    pub last_dialog: Mutex<String>,
+    /// `(tile_x, tile_y, item_id, amount)` of a pending `deposit_item`.
+    pub deposit: Mutex<(i32, i32, u32, u32)>,
+    /// Set when the storage a deposit targeted reported itself full.
+    pub deposit_failed: AtomicBool,
+    pub trade: Mutex<Option<Trade>>,
+    /// `(item_id, amount)` of a pending `trade_add_item`.
+    pub trade_add: Mutex<(u32, u32)>,
+    pub last_console_message: Mutex<String>,
+    pub last_variant: Mutex<Vec<u8>>,
+    pub warp_result: Mutex<Option<WarpResult>>,
+    pub pending_warp: Mutex<Option<(String, std::time::Instant)>>,
+    pub friends: Mutex<Option<Vec<Friend>>>,
+    pub friends_requested: AtomicBool,
+    /// Set while auto-collect is paused on a full inventory.
+    pub inventory_full: AtomicBool,
+    pub world_menu: Mutex<Option<WorldMenu>>,
+    pub route: Mutex<Option<Route>>,
+    pub route_running: AtomicBool,
+    pub early_actions: Mutex<VecDeque<EarlyAction>>,
+}
+
+/// A list of worlds visited in order, staying `dwell_ms` in each, looping.
+#[derive(Debug, Clone, Default)]
+pub struct Route {
+    pub worlds: Vec<String>,
+    pub dwell_ms: u64,
 }
 
 // ── Scripting & Callback System ─────────────────────────────────
 
 pub struct LuaCallback {
-    pub key: mlua::RegistryKey,
+    /// Shared so the function can be fetched after releasing the callbacks
+    /// lock; see [`crate::lua::release_key`].
+    pub key: Arc<mlua::RegistryKey>,
     pub once: bool,
 }
 
+/// A `setTimeout`/`setInterval` registration. `interval` is `None` for one-shot timers.
+pub struct LuaTimer {
+    pub key: Arc<mlua::RegistryKey>,
+    pub interval: Option<std::time::Duration>,
+    pub next_fire: std::time::Instant,
+}
+
+/// Result of [`Bot::run_script`]: log lines written while it ran and the Lua
+/// error, if it failed.
+#[derive(Debug, Clone)]
+pub struct ScriptRun {
+    pub logs: Vec<crate::LogEntry>,
+    pub error: Option<String>,
+}
+
 pub struct Scripting {
     pub data: Mutex<String>,
     pub currently_executing: AtomicBool,
+    /// Set by `stop_script`; the running script aborts at its next check.
+    pub stop_requested: AtomicBool,
     pub lua: Lua,
     pub callbacks: Mutex<HashMap<String, Vec<LuaCallback>>>,
+    pub timers: Mutex<HashMap<u32, LuaTimer>>,
+    pub next_timer_id: AtomicU32,
+    /// Backing file for the Lua `storage` table, opened on first use.
+    pub storage: Mutex<Option<ScriptStorage>>,
 }
 
 impl Default for Scripting {
@@ -90,28 +272,122 @@ impl Default for Scripting {
         Scripting {
             data: Mutex::new(String::new()),
             currently_executing: AtomicBool::new(false),
+            stop_requested: AtomicBool::new(false),
             lua: Lua::new(),
             callbacks: Mutex::new(HashMap::new()),
+            timers: Mutex::new(HashMap::new()),
+            next_timer_id: AtomicU32::new(1),
+            storage: Mutex::new(None),
         }
     }
 }
 
+impl Scripting {
+    /// Claims `currently_executing` for one script run and clears any stale
+    /// stop request. Returns `None` if a script is already running.
+    pub(crate) fn begin_run(&self) -> Option<ScriptGuard<'_>> {
+        self.currently_executing
+            .compare_exchange(false, true, Ordering::SeqCst, Ordering::SeqCst)
+            .ok()?;
+        self.stop_requested.store(false, Ordering::SeqCst);
+        Some(ScriptGuard(self))
+    }
+}
+
+/// Marks a script as running until dropped, so a panic unwinding out of the
+/// script doesn't leave every later run rejected.
+pub(crate) struct ScriptGuard<'a>(&'a Scripting);
+
+impl Drop for ScriptGuard<'_> {
+    fn drop(&mut self) {
+        self.0.stop_requested.store(false, Ordering::SeqCst);
+        self.0.currently_executing.store(false, Ordering::SeqCst);
+    }
+}
+
 // ── Lua UserData: BotArc ────────────────────────────────────────
 
 #[derive(Clone)]
 pub struct BotArc(pub Arc<Bot>);
 
+/// Resolves an item name for Lua, raising an error that names the item if unknown.
+fn item_id_by_name(bot: &Bot, name: &str) -> mlua::Result<u32> {
+    bot.world
+        .item_id_by_name(name)
+        .ok_or_else(|| mlua::Error::RuntimeError(format!("unknown item '{}'", name)))
+}
+
+/// Builds a dialog_return body from `{ dialog_name, values, button }`.
+fn dialog_return_from_lua(dialog: &mlua::Table) -> mlua::Result<String> {
+    let name: String = dialog.get("dialog_name")?;
+    let mut values = Vec::new();
+    if let Some(table) = dialog.get::<Option<mlua::Table>>("values")? {
+        for pair in table.pairs::<String, mlua::Value>() {
+            let (key, value) = pair?;
+            let value = match value {
+                mlua::Value::Boolean(checked) => (checked as u8).to_string(),
+                mlua::Value::Integer(i) => i.to_string(),
+                mlua::Value::Number(n) => n.to_string(),
+                mlua::Value::String(s) => s.to_str()?.to_string(),
+                other => {
+                    return Err(mlua::Error::RuntimeError(format!(
+                        "cannot send a {} as dialog value '{}'",
+                        other.type_name(),
+                        key
+                    )));
+                }
+            };
+            values.push((key, value));
+        }
+    }
+    // Lua tables are unordered; sort so the packet is deterministic.
+    values.sort();
+    if let Some(button) = dialog.get::<Option<String>>("button")? {
+        values.push(("buttonClicked".to_string(), button));
+    }
+    Ok(dialog::dialog_return(&name, &values))
+}
+
+/// Keeps the first few characters of a secret so values can still be told apart.
+fn redact(secret: &str) -> String {
+    if secret.is_empty() {
+        return String::new();
+    }
+    let prefix: String = secret.chars().take(4).collect();
+    format!("{}***", prefix)
+}
+
 impl UserData for BotArc {
     fn add_methods<M: UserDataMethods<Self>>(methods: &mut M) {
         // ── Actions ──
         methods.add_method("say", |_, this, message: String| {
-            this.0.say(&message);
+            this.0.say(ActionSource::Lua, &message);
             Ok(())
         });
         methods.add_method("warp", |_, this, world_name: String| {
-            this.0.warp(world_name);
+            this.0.warp(ActionSource::Lua, world_name);
             Ok(())
         });
+        // warpAndWait(world, timeout_ms?) -> entered, world name or reason
+        methods.add_method(
+            "warpAndWait",
+            |_, this, (world_name, timeout_ms): (String, Option<u64>)| {
+                let timeout_ms = timeout_ms.unwrap_or(this.0.config.world_join_timeout() as u64);
+                let timeout = std::time::Duration::from_millis(timeout_ms);
+                let result = this.0.warp_and_wait(ActionSource::Lua, world_name, timeout);
+                Ok(match result {
+                    WarpResult::Entered(world) => (true, world),
+                    other => (false, other.to_string()),
+                })
+            },
+        );
+        methods.add_method(
+            "warpAt",
+            |_, this, (world_name, x, y): (String, u32, u32)| {
+                this.0.warp_at(ActionSource::Lua, world_name, Some((x, y)));
+                Ok(())
+            },
+        );
         methods.add_method("leave", |_, this, ()| {
             this.0.leave();
             Ok(())
@@ -120,35 +396,168 @@ impl UserData for BotArc {
             this.0.disconnect();
             Ok(())
         });
+        methods.add_method("logout", |_, this, ()| {
+            this.0.logout();
+            Ok(())
+        });
+        methods.add_method("checkProxy", |lua, this, ()| {
+            let Some(check) = this.0.check_proxy() else {
+                return Ok(mlua::Value::Nil);
+            };
+            let table = lua.create_table()?;
+            table.set("ok", check.ok)?;
+            table.set("latencyMs", check.latency.as_millis() as u64)?;
+            table.set("error", check.error)?;
+            Ok(mlua::Value::Table(table))
+        });
+        methods.add_method("setProxy", |_, this, proxy: Option<String>| {
+            let cfg = proxy
+                .map(|proxy| crate::Socks5Config::parse(&proxy))
+                .transpose()
+                .map_err(mlua::Error::RuntimeError)?;
+            this.0.set_proxy(cfg);
+            Ok(())
+        });
+        methods.add_method("connectTo", |_, this, (host, port): (String, u16)| {
+            this.0.connect_to(&host, port).map_err(mlua::Error::from)
+        });
+        methods.add_method("lockContention", |lua, this, ()| {
+            let contention = this.0.runtime.lock_contention();
+            let table = lua.create_table()?;
+            table.set("world", contention.world)?;
+            table.set("inventory", contention.inventory)?;
+            Ok(table)
+        });
+        methods.add_method("idleTime", |_, this, ()| {
+            Ok(this.0.runtime.idle_time().as_millis() as u64)
+        });
         methods.add_method("punch", |_, this, (ox, oy): (i32, i32)| {
-            this.0.punch(ox, oy);
+            this.0.punch(ActionSource::Lua, ox, oy);
             Ok(())
         });
-        methods.add_method("place", |_, this, (ox, oy, id): (i32, i32, u32)| {
-            this.0.place(ox, oy, id, false);
+        methods.add_method("place", |_, this, (ox, oy, id): (i32, i32, Option<u32>)| {
+            let id = id.or_else(|| this.0.held_item()).ok_or_else(|| {
+                mlua::Error::RuntimeError("place needs an item id when nothing is held".to_string())
+            })?;
+            this.0.place(ActionSource::Lua, ox, oy, id);
+            Ok(())
+        });
+        methods.add_method("canBuildAt", |_, this, (x, y): (i32, i32)| {
+            Ok(this.0.can_build_at(x, y))
+        });
+        methods.add_method("setBuildAccessCheck", |_, this, enabled: bool| {
+            this.0.config.set_check_build_access(enabled);
             Ok(())
         });
+        methods.add_method("batchPlace", |lua, this, placements: Vec<mlua::Table>| {
+            let placements = placements
+                .iter()
+                .map(|p| Ok((p.get("x")?, p.get("y")?, p.get("id")?)))
+                .collect::<mlua::Result<Vec<(i32, i32, u32)>>>()?;
+            let results = this.0.batch_place(ActionSource::Lua, placements);
+            lua.create_sequence_from(results.iter().map(|result| result.as_str()))
+        });
+        methods.add_method("holdItem", |_, this, id: u32| {
+            Ok(this.0.hold_item(id).is_ok())
+        });
         methods.add_method("wrench", |_, this, (ox, oy): (i32, i32)| {
-            this.0.wrench(ox, oy);
+            this.0.wrench(ActionSource::Lua, ox, oy);
             Ok(())
         });
+        methods.add_method("refreshInventory", |_, this, ()| {
+            Ok(this.0.refresh_inventory())
+        });
+        methods.add_method("setStatusText", |_, this, text: String| {
+            Ok(this.0.set_status_text(&text))
+        });
+        methods.add_method("punchPlayer", |_, this, net_id: u32| {
+            Ok(this.0.punch_player(ActionSource::Lua, net_id))
+        });
         methods.add_method("wrenchPlayer", |_, this, net_id: u32| {
-            this.0.wrench_player(net_id);
+            this.0.wrench_player(ActionSource::Lua, net_id);
             Ok(())
         });
+        methods.add_method("ownsWorld", |_, this, ()| Ok(this.0.owns_world()));
+        methods.add_method("kickPlayer", |_, this, net_id: u32| {
+            this.0.kick_player(net_id).map_err(mlua::Error::from)
+        });
+        methods.add_method("pullPlayer", |_, this, net_id: u32| {
+            this.0.pull_player(net_id).map_err(mlua::Error::from)
+        });
         methods.add_method("wear", |_, this, item_id: u32| {
             this.0.wear(item_id);
             Ok(())
         });
+        methods.add_method("toggleWear", |_, this, item_id: u32| {
+            Ok(this.0.toggle_wear(item_id))
+        });
+        methods.add_method(
+            "depositItem",
+            |_, this, (ox, oy, id, amount): (i32, i32, u32, u32)| {
+                Ok(this.0.deposit_item(ActionSource::Lua, ox, oy, id, amount))
+            },
+        );
         methods.add_method("drop", |_, this, (id, amount): (u32, u32)| {
-            this.0.drop_item(id, amount);
+            this.0.drop_item(ActionSource::Lua, id, amount);
             Ok(())
         });
         methods.add_method("trash", |_, this, (id, amount): (u32, u32)| {
-            this.0.trash_item(id, amount);
+            this.0.trash_item(ActionSource::Lua, id, amount);
+            Ok(())
+        });
+        methods.add_method("dropByName", |_, this, (name, amount): (String, u32)| {
+            let id = item_id_by_name(&this.0, &name)?;
+            this.0.drop_item(ActionSource::Lua, id, amount);
             Ok(())
         });
-        methods.add_method("collect", |_, this, ()| Ok(this.0.collect()));
+        methods.add_method("trashByName", |_, this, (name, amount): (String, u32)| {
+            let id = item_id_by_name(&this.0, &name)?;
+            this.0.trash_item(ActionSource::Lua, id, amount);
+            Ok(())
+        });
+        methods.add_method("collectItem", |_, this, uid: u32| {
+            Ok(this.0.collect_item(uid))
+        });
+        methods.add_method("collect", |_, this, opts: Option<mlua::Table>| {
+            let strategy = match opts {
+                Some(opts) => match opts.get::<Option<String>>("strategy")? {
+                    Some(name) => CollectStrategy::from_name(&name).ok_or_else(|| {
+                        mlua::Error::RuntimeError(format!("unknown collect strategy '{}'", name))
+                    })?,
+                    None => this.0.config.collect_strategy(),
+                },
+                None => this.0.config.collect_strategy(),
+            };
+            Ok(this.0.collect_with(ActionSource::Lua, strategy))
+        });
+        methods.add_method("farmCycle", |lua, this, opts: mlua::Table| {
+            let defaults = FarmConfig::default();
+            let config = FarmConfig {
+                container: (opts.get("x")?, opts.get("y")?),
+                items: opts.get("items")?,
+                max_cycles: opts
+                    .get::<Option<u32>>("maxCycles")?
+                    .unwrap_or(defaults.max_cycles),
+                step_delay_ms: opts
+                    .get::<Option<u64>>("delay")?
+                    .unwrap_or(defaults.step_delay_ms),
+            };
+            let report = this.0.farm_cycle(ActionSource::Lua, &config);
+
+            let cycles = lua.create_table()?;
+            for (i, stats) in report.cycles.iter().enumerate() {
+                let entry = lua.create_table()?;
+                entry.set("collected", stats.collected)?;
+                entry.set("deposited", stats.deposited)?;
+                cycles.set(i + 1, entry)?;
+            }
+            let table = lua.create_table()?;
+            table.set("cycles", cycles)?;
+            table.set("collected", report.collected())?;
+            table.set("deposited", report.deposited())?;
+            table.set("stop", report.stop.as_str())?;
+            Ok(table)
+        });
         methods.add_method("acceptAccess", |_, this, ()| {
             this.0.accept_access();
             Ok(())
@@ -158,8 +567,81 @@ impl UserData for BotArc {
             this.0.enter_door(ox, oy);
             Ok(())
         });
+        methods.add_method("startTrade", |_, this, net_id: u32| {
+            this.0.start_trade(ActionSource::Lua, net_id);
+            Ok(())
+        });
+        methods.add_method("tradeAdd", |_, this, (id, amount): (u32, u32)| {
+            Ok(this.0.trade_add_item(id, amount).is_ok())
+        });
+        methods.add_method("tradeAccept", |_, this, ()| {
+            Ok(this.0.trade_accept(ActionSource::Lua).is_ok())
+        });
+        methods.add_method("tradeCancel", |_, this, ()| {
+            this.0.trade_cancel();
+            Ok(())
+        });
+        methods.add_method("getTrade", |lua, this, ()| {
+            let trade = this.0.temporary_data.trade.lock().unwrap().clone();
+            let Some(trade) = trade else {
+                return Ok(None);
+            };
+            let offer = |items: &[(u16, u32)]| -> mlua::Result<mlua::Table> {
+                let table = lua.create_table()?;
+                for &(id, amount) in items {
+                    let entry = lua.create_table()?;
+                    entry.set("id", id)?;
+                    entry.set("amount", amount)?;
+                    table.push(entry)?;
+                }
+                Ok(table)
+            };
+            let t = lua.create_table()?;
+            t.set("partnerName", trade.partner_name.clone())?;
+            t.set("partnerNetId", trade.partner_net_id)?;
+            t.set("ourOffer", offer(&trade.our_offer)?)?;
+            t.set("theirOffer", offer(&trade.their_offer)?)?;
+            t.set("accepted", trade.accepted)?;
+            t.set("partnerAccepted", trade.partner_accepted)?;
+            Ok(Some(t))
+        });
+        methods.add_method("sendDialog", |_, this, dialog: mlua::Table| {
+            this.0
+                .send_dialog(ActionSource::Lua, &dialog_return_from_lua(&dialog)?);
+            Ok(())
+        });
         methods.add_method("sendDialogReturn", |_, this, data: String| {
-            this.0.send_dialog_return(&data);
+            this.0.send_dialog(ActionSource::Lua, &data);
+            Ok(())
+        });
+        // respondDialog(body) sends a raw dialog_return body; a table is
+        // built the same way as sendDialog.
+        methods.add_method("respondDialog", |_, this, payload: mlua::Value| {
+            let body = match payload {
+                mlua::Value::String(s) => s.to_str()?.to_string(),
+                mlua::Value::Table(dialog) => dialog_return_from_lua(&dialog)?,
+                other => {
+                    return Err(mlua::Error::RuntimeError(format!(
+                        "cannot respond to a dialog with a {}",
+                        other.type_name()
+                    )));
+                }
+            };
+            this.0.send_dialog(ActionSource::Lua, &body);
+            Ok(())
+        });
+        methods.add_method("getFriends", |lua, this, ()| {
+            let table = lua.create_table()?;
+            for (i, friend) in this.0.friends().into_iter().enumerate() {
+                let entry = lua.create_table()?;
+                entry.set("name", friend.name)?;
+                entry.set("online", friend.online)?;
+                table.set(i + 1, entry)?;
+            }
+            Ok(table)
+        });
+        methods.add_method("refreshFriends", |_, this, ()| {
+            this.0.refresh_friends();
             Ok(())
         });
 
@@ -174,6 +656,10 @@ impl UserData for BotArc {
         });
 
         // ── Config ──
+        methods.add_method("setPauseCollectWhileMoving", |_, this, on: bool| {
+            this.0.set_pause_collect_while_moving(on);
+            Ok(())
+        });
         methods.add_method("setAutoCollect", |_, this, on: bool| {
             this.0.set_auto_collect(on);
             Ok(())
@@ -182,6 +668,171 @@ impl UserData for BotArc {
             this.0.set_auto_reconnect(on);
             Ok(())
         });
+        methods.add_method(
+            "setLeaveAction",
+            |_, this, (rule, action, world): (String, String, Option<String>)| {
+                let action = LeaveAction::from_name(&action, world).ok_or_else(|| {
+                    mlua::Error::RuntimeError(format!(
+                        "invalid leave action '{}' (warp needs a world name)",
+                        action
+                    ))
+                })?;
+                let mut rules = this.0.config.leave_rules();
+                match rule.to_lowercase().as_str() {
+                    "mod" => rules.on_mod = action,
+                    "invisible" => rules.on_invisible = action,
+                    other => {
+                        return Err(mlua::Error::RuntimeError(format!(
+                            "unknown leave rule '{}', expected mod/invisible",
+                            other
+                        )));
+                    }
+                }
+                this.0.config.set_leave_rules(rules);
+                Ok(())
+            },
+        );
+        methods.add_method(
+            "setInventoryFullAction",
+            |_, this, (action, world): (String, Option<String>)| {
+                let action = LeaveAction::from_name(&action, world).ok_or_else(|| {
+                    mlua::Error::RuntimeError(format!(
+                        "invalid inventory full action '{}' (warp needs a world name)",
+                        action
+                    ))
+                })?;
+                this.0.config.set_inventory_full_action(action);
+                Ok(())
+            },
+        );
+        methods.add_method(
+            "setReconnectPolicy",
+            |_, this, (reason, action, secs): (String, String, Option<u64>)| {
+                let reason = DisconnectReason::from_name(&reason).ok_or_else(|| {
+                    mlua::Error::RuntimeError(format!(
+                        "unknown disconnect reason '{}', expected \
+                         timeout/proxy_error/kicked/banned/suspended/requested",
+                        reason
+                    ))
+                })?;
+                let action = ReconnectAction::from_name(&action, secs).ok_or_else(|| {
+                    mlua::Error::RuntimeError(format!(
+                        "invalid reconnect action '{}', expected immediate/backoff/never",
+                        action
+                    ))
+                })?;
+                this.0.set_reconnect_action(reason, action);
+                Ok(())
+            },
+        );
+        methods.add_method("goHome", |_, this, ()| Ok(this.0.go_home()));
+        methods.add_method(
+            "setRoute",
+            |_, this, (worlds, dwell_ms): (Vec<String>, u64)| {
+                this.0.set_route(worlds, dwell_ms);
+                Ok(())
+            },
+        );
+        methods.add_method("startRoute", |_, this, ()| Ok(this.0.start_route()));
+        methods.add_method("stopRoute", |_, this, ()| {
+            this.0.stop_route();
+            Ok(())
+        });
+        methods.add_method(
+            "setWarpAllowlist",
+            |_, this, worlds: Option<Vec<String>>| {
+                this.0.set_warp_allowlist(worlds.unwrap_or_default());
+                Ok(())
+            },
+        );
+        methods.add_method("setKickPatterns", |_, this, patterns: Vec<String>| {
+            let mut rules = this.0.config.kick_rules();
+            rules.patterns = patterns;
+            this.0.config.set_kick_rules(rules);
+            Ok(())
+        });
+        methods.add_method("setKickCooldown", |_, this, seconds: u64| {
+            let mut rules = this.0.config.kick_rules();
+            rules.cooldown_secs = seconds;
+            this.0.config.set_kick_rules(rules);
+            Ok(())
+        });
+        methods.add_method("setRejectEarlyActions", |_, this, reject: bool| {
+            this.0.set_reject_early_actions(reject);
+            Ok(())
+        });
+        methods.add_method("setDebug", |_, this, enabled: bool| {
+            this.0.config.set_debug(enabled);
+            Ok(())
+        });
+        methods.add_method("lastVariant", |lua, this, ()| {
+            let data = this.0.temporary_data.last_variant.lock().unwrap().clone();
+            match VariantList::deserialize(&data) {
+                Ok(variant) => Ok(Some(variant_list_to_lua_table(lua, &variant)?)),
+                Err(_) => Ok(None),
+            }
+        });
+        methods.add_method("serverData", |lua, this, redact_secrets: Option<bool>| {
+            let (server, port) = {
+                let server_data = this.0.auth.server_data();
+                match server_data.as_ref() {
+                    Some(data) => (data.server.clone(), data.port),
+                    None => return Ok(None),
+                }
+            };
+            let (door_id, uuid, aat) = {
+                let info = this.0.auth.login_info();
+                info.as_ref()
+                    .map(|i| (i.door_id.clone(), i.uuid.clone(), i.aat.clone()))
+                    .unwrap_or_default()
+            };
+            // The uuid and aat come from OnSendToServer and are replayed in the
+            // login packet for the redirect, so treat both as session secrets.
+            let (uuid, aat) = if redact_secrets.unwrap_or(true) {
+                (redact(&uuid), redact(&aat))
+            } else {
+                (uuid, aat)
+            };
+
+            let table = lua.create_table()?;
+            table.set("server", server)?;
+            table.set("port", port)?;
+            table.set("door_id", door_id)?;
+            table.set("uuid", uuid)?;
+            table.set("aat", aat)?;
+            Ok(Some(table))
+        });
+        methods.add_method("setGemsThreshold", |_, this, threshold: Option<i32>| {
+            this.0.config.set_gems_threshold(threshold);
+            Ok(())
+        });
+        methods.add_method("setWarpTimeout", |_, this, ms: u32| {
+            this.0.set_world_join_timeout(ms);
+            Ok(())
+        });
+        methods.add_method("setCollectStrategy", |_, this, name: String| {
+            let strategy = CollectStrategy::from_name(&name).ok_or_else(|| {
+                mlua::Error::RuntimeError(format!("unknown collect strategy '{}'", name))
+            })?;
+            this.0.config.set_collect_strategy(strategy);
+            Ok(())
+        });
+        methods.add_method("setCollectRadius", |_, this, tiles: Option<u32>| {
+            this.0.config.set_collect_radius(tiles);
+            Ok(())
+        });
+        methods.add_method("setCollectFilter", |_, this, ids: Option<Vec<u16>>| {
+            this.0.config.set_collect_filter(ids);
+            Ok(())
+        });
+        methods.add_method("setIdleDisconnect", |_, this, ms: Option<u32>| {
+            this.0.set_idle_disconnect(ms);
+            Ok(())
+        });
+        methods.add_method("setKeepaliveInterval", |_, this, ms: Option<u32>| {
+            this.0.set_keepalive_interval(ms);
+            Ok(())
+        });
         methods.add_method("setFindPathDelay", |_, this, ms: u32| {
             this.0.set_findpath_delay(ms);
             Ok(())
@@ -194,6 +845,10 @@ impl UserData for BotArc {
             this.0.set_place_delay(ms);
             Ok(())
         });
+        methods.add_method("setUnknownItemFormat", |_, this, format: String| {
+            this.0.world.set_unknown_item_format(format);
+            Ok(())
+        });
 
         // ── Raw Packets ──
         methods.add_method(
@@ -218,22 +873,44 @@ impl UserData for BotArc {
 
         // ── Event System ──
         methods.add_method("on", |lua, this, (event, func): (String, mlua::Function)| {
-            let key = lua.create_registry_value(func)?;
+            let key = Arc::new(lua.create_registry_value(func)?);
             let mut cbs = this.0.scripting.callbacks.lock().unwrap();
             cbs.entry(event).or_default().push(LuaCallback { key, once: false });
             Ok(())
         });
         methods.add_method("once", |lua, this, (event, func): (String, mlua::Function)| {
-            let key = lua.create_registry_value(func)?;
+            let key = Arc::new(lua.create_registry_value(func)?);
             let mut cbs = this.0.scripting.callbacks.lock().unwrap();
             cbs.entry(event).or_default().push(LuaCallback { key, once: true });
             Ok(())
         });
+        // onDialog(func, once?) keeps a single handler called with each
+        // dialog's text, replacing any earlier one; onDialog(nil) clears it.
+        methods.add_method(
+            "onDialog",
+            |lua, this, (func, once): (Option<mlua::Function>, Option<bool>)| {
+                let mut cbs = this.0.scripting.callbacks.lock().unwrap();
+                if let Some(previous) = cbs.remove(crate::lua::DIALOG_HANDLER) {
+                    for cb in previous {
+                        crate::lua::release_key(lua, cb.key)?;
+                    }
+                }
+                if let Some(func) = func {
+                    let key = Arc::new(lua.create_registry_value(func)?);
+                    let once = once.unwrap_or(false);
+                    cbs.insert(
+                        crate::lua::DIALOG_HANDLER.to_string(),
+                        vec![LuaCallback { key, once }],
+                    );
+                }
+                Ok(())
+            },
+        );
         methods.add_method("removeListener", |lua, this, event: String| {
             let mut cbs = this.0.scripting.callbacks.lock().unwrap();
             if let Some(callbacks) = cbs.remove(&event) {
                 for cb in callbacks {
-                    lua.remove_registry_value(cb.key)?;
+                    crate::lua::release_key(lua, cb.key)?;
                 }
             }
             Ok(())
@@ -242,7 +919,7 @@ impl UserData for BotArc {
             let mut cbs = this.0.scripting.callbacks.lock().unwrap();
             for (_, callbacks) in cbs.drain() {
                 for cb in callbacks {
-                    lua.remove_registry_value(cb.key)?;
+                    crate::lua::release_key(lua, cb.key)?;
                 }
             }
             Ok(())
@@ -262,6 +939,9 @@ impl UserData for BotArc {
             Ok(t)
         });
         fields.add_field_method_get("gems", |_, this| Ok(this.0.inventory.gems()));
+        fields.add_field_method_get("heldItem", |_, this| Ok(this.0.held_item()));
+        fields.add_field_method_get("gravity", |_, this| Ok(this.0.movement.gravity()));
+        fields.add_field_method_get("motd", |_, this| Ok(this.0.runtime.motd()));
         fields.add_field_method_get("netId", |_, this| Ok(this.0.runtime.net_id()));
         fields.add_field_method_get("userId", |_, this| Ok(this.0.runtime.user_id()));
         fields.add_field_method_get("name", |_, this| {
@@ -283,7 +963,10 @@ impl UserData for BotArc {
         fields.add_field_method_get("isInWorld", |_, this| {
             let in_world = match this.0.world.data.try_lock() {
                 Ok(w) => w.name != "EXIT",
-                Err(_) => false,
+                Err(_) => {
+                    this.0.runtime.record_world_lock_miss();
+                    false
+                }
             };
             Ok(in_world)
         });
@@ -450,11 +1133,103 @@ impl UserData for LuaInventory {
             for (i, (id, item)) in items.iter().enumerate() {
                 let entry = lua.create_table()?;
                 entry.set("id", *id as u32)?;
+                entry.set("name", this.0.world.item_name(*id as u32))?;
                 entry.set("amount", item.amount as u32)?;
                 table.set(i + 1, entry)?;
             }
             Ok(table)
         });
+        methods.add_method("getItemsSorted", |lua, this, by: Option<String>| {
+            let mut items: Vec<(u16, u8, String)> = this
+                .0
+                .inventory
+                .get_all_items()
+                .into_iter()
+                .map(|(id, item)| (id, item.amount, this.0.world.item_name(id as u32)))
+                .collect();
+
+            match by.as_deref().unwrap_or("id") {
+                "id" => items.sort_by_key(|(id, _, _)| *id),
+                "amount" => items.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(&b.0))),
+                "name" => items.sort_by(|a, b| a.2.to_lowercase().cmp(&b.2.to_lowercase())),
+                other => {
+                    return Err(mlua::Error::RuntimeError(format!(
+                        "unknown sort key '{}', expected id/amount/name",
+                        other
+                    )));
+                }
+            }
+
+            let table = lua.create_table()?;
+            for (i, (id, amount, name)) in items.into_iter().enumerate() {
+                let entry = lua.create_table()?;
+                entry.set("id", id as u32)?;
+                entry.set("name", name)?;
+                entry.set("amount", amount as u32)?;
+                table.set(i + 1, entry)?;
+            }
+            Ok(table)
+        });
+        methods.add_method("filter", |lua, this, predicate: mlua::Function| {
+            let items = this.0.inventory.get_all_items();
+            let table = lua.create_table()?;
+            let mut index = 1;
+            for (id, item) in items {
+                let entry = lua.create_table()?;
+                entry.set("id", id as u32)?;
+                entry.set("name", this.0.world.item_name(id as u32))?;
+                entry.set("amount", item.amount as u32)?;
+                if predicate.call::<bool>(entry.clone())? {
+                    table.set(index, entry)?;
+                    index += 1;
+                }
+            }
+            Ok(table)
+        });
+        methods.add_method("snapshot", |lua, this, ()| {
+            let Some(snapshot) = this.0.inventory.try_get_snapshot() else {
+                return Ok(mlua::Value::Nil);
+            };
+
+            let mut item_amounts: Vec<(u16, u8)> = snapshot.item_amounts.into_iter().collect();
+            item_amounts.sort_by_key(|(id, _)| *id);
+
+            let items = lua.create_table()?;
+            for (i, (id, amount)) in item_amounts.into_iter().enumerate() {
+                let entry = lua.create_table()?;
+                entry.set("id", id as u32)?;
+                entry.set("name", this.0.world.item_name(id as u32))?;
+                entry.set("amount", amount as u32)?;
+                items.set(i + 1, entry)?;
+            }
+
+            let table = lua.create_table()?;
+            table.set("size", snapshot.size)?;
+            table.set("count", snapshot.item_count)?;
+            table.set("gems", this.0.inventory.gems())?;
+            table.set("items", items)?;
+            Ok(mlua::Value::Table(table))
+        });
+        methods.add_method("refresh", |_, this, ()| Ok(this.0.refresh_inventory()));
+        methods.add_method(
+            "hasSpaceFor",
+            |_, this, (id, amount): (u32, Option<u32>)| {
+                let max_stack = {
+                    let db = this.0.world.item_database.read().unwrap();
+                    db.get_item(&id)
+                        .map(|item| item.max_amount as u32)
+                        .unwrap_or(0)
+                };
+                // Unknown stack size: be conservative rather than guess.
+                if max_stack == 0 {
+                    return Ok(false);
+                }
+                Ok(this
+                    .0
+                    .inventory
+                    .has_space_for(id as u16, amount.unwrap_or(1), max_stack))
+            },
+        );
         methods.add_method("getSize", |_, this, ()| {
             let (size, _) = this.0.inventory.size_and_count();
             Ok(size)
@@ -474,6 +1249,7 @@ impl UserData for LuaInventory {
             } else {
                 let entry = lua.create_table()?;
                 entry.set("id", id)?;
+                entry.set("name", this.0.world.item_name(id))?;
                 entry.set("amount", count as u32)?;
                 Ok(mlua::Value::Table(entry))
             }
@@ -499,27 +1275,8 @@ impl UserData for LuaWorld {
     fn add_methods<M: UserDataMethods<Self>>(methods: &mut M) {
         methods.add_method("getTile", |_, this, (x, y): (u32, u32)| {
             let world = this.0.world.data.lock().unwrap();
-            if let Some(tile) = world.get_tile(x, y) {
-                let is_seed = matches!(tile.tile_type, gtworld_r::TileType::Seed { .. });
-                let has_lock = matches!(tile.tile_type, gtworld_r::TileType::Lock { .. });
-                let collision_type = {
-                    let db = this.0.world.item_database.read().unwrap();
-                    db.get_item(&(tile.foreground_item_id as u32))
-                        .map(|i| i.collision_type)
-                        .unwrap_or(0)
-                };
-                Ok(Some(LuaTile {
-                    x: tile.x,
-                    y: tile.y,
-                    foreground: tile.foreground_item_id,
-                    background: tile.background_item_id,
-                    collision_type,
-                    is_seed,
-                    has_lock,
-                }))
-            } else {
-                Ok(None)
-            }
+            let db = this.0.world.item_database.read().unwrap();
+            Ok(world.get_tile(x, y).map(|tile| LuaTile::new(tile, &db)))
         });
         methods.add_method("getTiles", |_, this, ()| {
             let world = this.0.world.data.lock().unwrap();
@@ -527,40 +1284,100 @@ impl UserData for LuaWorld {
             let tiles: Vec<LuaTile> = world
                 .tiles
                 .iter()
-                .map(|tile| {
-                    let is_seed = matches!(tile.tile_type, gtworld_r::TileType::Seed { .. });
-                    let has_lock = matches!(tile.tile_type, gtworld_r::TileType::Lock { .. });
-                    let collision_type = db
-                        .get_item(&(tile.foreground_item_id as u32))
-                        .map(|i| i.collision_type)
-                        .unwrap_or(0);
-                    LuaTile {
-                        x: tile.x,
-                        y: tile.y,
-                        foreground: tile.foreground_item_id,
-                        background: tile.background_item_id,
-                        collision_type,
-                        is_seed,
-                        has_lock,
-                    }
-                })
+                .map(|tile| LuaTile::new(tile, &db))
                 .collect();
             Ok(tiles)
         });
+        methods.add_method("getMainLock", |_, this, ()| {
+            let Some((x, y)) = this.0.world.main_lock_position() else {
+                return Ok(None);
+            };
+            let world = this.0.world.data.lock().unwrap();
+            let db = this.0.world.item_database.read().unwrap();
+            Ok(world.get_tile(x, y).map(|tile| LuaTile::new(tile, &db)))
+        });
+        methods.add_method("getTilesById", |_, this, item_id: u16| {
+            let world = this.0.world.data.lock().unwrap();
+            let db = this.0.world.item_database.read().unwrap();
+            let tiles: Vec<LuaTile> = world
+                .tiles
+                .iter()
+                .filter(|tile| tile.foreground_item_id == item_id)
+                .map(|tile| LuaTile::new(tile, &db))
+                .collect();
+            Ok(tiles)
+        });
+        methods.add_method("getClosestTile", |_, this, item_id: u16| {
+            let pos = this.0.movement.position();
+            let (bx, by) = ((pos.0 / 32.0).floor() as i64, (pos.1 / 32.0).floor() as i64);
+            let world = this.0.world.data.lock().unwrap();
+            let db = this.0.world.item_database.read().unwrap();
+            let closest = world
+                .tiles
+                .iter()
+                .filter(|tile| tile.foreground_item_id == item_id)
+                .min_by_key(|tile| {
+                    let (dx, dy) = (tile.x as i64 - bx, tile.y as i64 - by);
+                    dx * dx + dy * dy
+                });
+            Ok(closest.map(|tile| LuaTile::new(tile, &db)))
+        });
+        methods.add_method("checkpoint", |lua, this, ()| {
+            match this.0.world.checkpoint() {
+                Some((x, y)) => {
+                    let t = lua.create_table()?;
+                    t.set("x", x)?;
+                    t.set("y", y)?;
+                    Ok(Some(t))
+                }
+                None => Ok(None),
+            }
+        });
+        methods.add_method("weather", |_, this, ()| Ok(this.0.world.weather()));
+        methods.add_method("background", |_, this, ()| Ok(this.0.world.background()));
+        methods.add_method("favorites", |_, this, ()| {
+            let menu = this.0.temporary_data.world_menu.lock().unwrap();
+            Ok(menu
+                .as_ref()
+                .map(|menu| menu.favorites.clone())
+                .unwrap_or_default())
+        });
+        methods.add_method("recent", |_, this, ()| {
+            let menu = this.0.temporary_data.world_menu.lock().unwrap();
+            Ok(menu
+                .as_ref()
+                .map(|menu| menu.recent.clone())
+                .unwrap_or_default())
+        });
+        methods.add_method("countTiles", |_, this, item_id: u16| {
+            Ok(this.0.world.count_tiles(item_id))
+        });
+        methods.add_method("tileHistogram", |_, this, ()| {
+            Ok(this.0.world.tile_histogram())
+        });
+        methods.add_method("pathLength", |_, this, (x, y): (u32, u32)| {
+            Ok(this.0.path_length(x, y))
+        });
+        methods.add_method("resolveNetId", |_, this, net_id: u32| {
+            let players = this.0.world.players.lock().unwrap();
+            Ok(players.get(&net_id).map(LuaPlayer::from))
+        });
+        methods.add_method("spawnPos", |lua, this, ()| {
+            match this.0.world.spawn_position() {
+                Some((x, y)) => {
+                    let t = lua.create_table()?;
+                    t.set("x", x)?;
+                    t.set("y", y)?;
+                    Ok(Some(t))
+                }
+                None => Ok(None),
+            }
+        });
         methods.add_method("getPlayers", |lua, this, ()| {
             let players = this.0.world.players.lock().unwrap();
             let table = lua.create_table()?;
             for (i, (_, player)) in players.iter().enumerate() {
-                table.set(i + 1, LuaPlayer {
-                    name: player.name.clone(),
-                    net_id: player.net_id,
-                    user_id: player.user_id,
-                    country: player.country.clone(),
-                    pos_x: player.position.0,
-                    pos_y: player.position.1,
-                    invisible: player.invisible,
-                    is_mod: player.m_state == 1,
-                })?;
+                table.set(i + 1, LuaPlayer::from(player))?;
             }
             Ok(table)
         });
@@ -577,6 +1394,19 @@ impl UserData for LuaWorld {
                 is_mod: p.m_state == 1,
             }))
         });
+        methods.add_method("getEntities", |lua, this, ()| {
+            let entities = this.0.world.entities.lock().unwrap();
+            let table = lua.create_table()?;
+            for (i, entity) in entities.values().enumerate() {
+                let entry = lua.create_table()?;
+                entry.set("net_id", entity.net_id)?;
+                entry.set("type", entity.kind.as_str())?;
+                entry.set("x", entity.position.0)?;
+                entry.set("y", entity.position.1)?;
+                table.set(i + 1, entry)?;
+            }
+            Ok(table)
+        });
         methods.add_method("getDroppedItems", |lua, this, ()| {
             let world = this.0.world.data.lock().unwrap();
             let table = lua.create_table()?;
@@ -621,11 +1451,64 @@ pub struct LuaTile {
     pub foreground: u16,
     pub background: u16,
     pub collision_type: u8,
+    /// Action type of the foreground item, used for the hazard checks.
+    pub action_type: u8,
     pub is_seed: bool,
     pub has_lock: bool,
+    pub lock_owner: Option<u32>,
+    pub is_main_lock: bool,
+}
+
+/// Item action types that hurt the player on contact.
+const ACTION_TYPE_DEADLY_BLOCK: u8 = 6;
+const ACTION_TYPE_LAVA: u8 = 16;
+
+impl LuaTile {
+    fn is_lava(&self) -> bool {
+        self.action_type == ACTION_TYPE_LAVA
+    }
+
+    fn is_hazard(&self) -> bool {
+        self.is_lava() || self.action_type == ACTION_TYPE_DEADLY_BLOCK
+    }
+
+    fn new(tile: &gtworld_r::Tile, db: &ItemDatabase) -> Self {
+        let (collision_type, action_type) = db
+            .get_item(&(tile.foreground_item_id as u32))
+            .map(|i| (i.collision_type, i.action_type))
+            .unwrap_or((0, 0));
+        LuaTile {
+            x: tile.x,
+            y: tile.y,
+            foreground: tile.foreground_item_id,
+            background: tile.background_item_id,
+            collision_type,
+            action_type,
+            is_seed: matches!(tile.tile_type, gtworld_r::TileType::Seed { .. }),
+            has_lock: matches!(tile.tile_type, gtworld_r::TileType::Lock { .. }),
+            lock_owner: match &tile.tile_type {
+                gtworld_r::TileType::Lock { owner_uid, .. } => Some(*owner_uid),
+                _ => None,
+            },
+            is_main_lock: game_world::is_main_lock(tile),
+        }
+    }
 }
 
 impl UserData for LuaTile {
+    fn add_methods<M: UserDataMethods<Self>>(methods: &mut M) {
+        methods.add_method("distanceTo", |_, this, (x, y): (f64, f64)| {
+            let (dx, dy) = (this.x as f64 - x, this.y as f64 - y);
+            Ok((dx * dx + dy * dy).sqrt())
+        });
+        methods.add_method("center", |_, this, ()| {
+            Ok(LuaPosition(
+                (this.x * 32 + 16) as f32,
+                (this.y * 32 + 16) as f32,
+            ))
+        });
+    }
+
     fn add_fields<F: UserDataFields<Self>>(fields: &mut F) {
         fields.add_field_method_get("x", |_, this| Ok(this.x));
         fields.add_field_method_get("y", |_, this| Ok(this.y));
@@ -635,7 +1518,11 @@ impl UserData for LuaTile {
             Ok(this.collision_type == 1 || this.collision_type == 6)
         });
         fields.add_field_method_get("collisionType", |_, this| Ok(this.collision_type));
+        fields.add_field_method_get("isLava", |_, this| Ok(this.is_lava()));
+        fields.add_field_method_get("isHazard", |_, this| Ok(this.is_hazard()));
         fields.add_field_method_get("hasLock", |_, this| Ok(this.has_lock));
+        fields.add_field_method_get("lockOwnerId", |_, this| Ok(this.lock_owner));
+        fields.add_field_method_get("isMainLock", |_, this| Ok(this.is_main_lock));
         fields.add_field_method_get("isSeed", |_, this| Ok(this.is_seed));
     }
 }
@@ -654,6 +1541,21 @@ pub struct LuaPlayer {
     pub is_mod: bool,
 }
 
+impl From<&Player> for LuaPlayer {
+    fn from(player: &Player) -> Self {
+        LuaPlayer {
+            name: player.name.clone(),
+            net_id: player.net_id,
+            user_id: player.user_id,
+            country: player.country.clone(),
+            pos_x: player.position.0,
+            pos_y: player.position.1,
+            invisible: player.invisible,
+            is_mod: player.m_state == 1,
+        }
+    }
+}
+
 impl UserData for LuaPlayer {
     fn add_fields<F: UserDataFields<Self>>(fields: &mut F) {
         fields.add_field_method_get("name", |_, this| Ok(this.name.clone()));
@@ -0,0 +1,109 @@
+/// Tiles the bot can place into around the tile it stands on.
+pub const PLACE_REACH: i32 = 4;
+
+/// Outcome of one placement in `Bot::batch_place`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PlaceResult {
+    Placed,
+    /// The bot ran out of the item before reaching this placement.
+    MissingItem,
+    /// No path to the tile the bot would have placed it from.
+    Unreachable,
+    /// The running script was asked to stop.
+    Stopped,
+    /// Nothing was sent: the tile is the one the bot stands on, build access
+    /// was denied or it was out of reach.
+    Skipped,
+    /// The bot wasn't in game, e.g. it disconnected partway through.
+    NotReady,
+}
+
+impl PlaceResult {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            PlaceResult::Placed => "placed",
+            PlaceResult::MissingItem => "missing_item",
+            PlaceResult::Unreachable => "unreachable",
+            PlaceResult::Stopped => "stopped",
+            PlaceResult::Skipped => "skipped",
+            PlaceResult::NotReady => "not_ready",
+        }
+    }
+}
+
+/// A tile to stand on and the placements made from it, as indexes into the
+/// batch.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PlaceCluster {
+    pub stand: (i32, i32),
+    pub placements: Vec<usize>,
+}
+
+/// Groups `(x, y, item_id)` placements into clusters the bot can place from
+/// one spot, visiting the nearest remaining placement each time. Within a
+/// cluster the farthest tiles come first, so the tile the bot stands on is
+/// placed last.
+pub fn plan_clusters(placements: &[(i32, i32, u32)], start: (i32, i32)) -> Vec<PlaceCluster> {
+    let distance = |a: (i32, i32), b: (i32, i32)| (a.0 - b.0).abs().max((a.1 - b.1).abs());
+
+    let mut remaining: Vec<usize> = (0..placements.len()).collect();
+    let mut clusters = Vec::new();
+    let mut position = start;
+
+    while let Some(&nearest) = remaining
+        .iter()
+        .min_by_key(|&&i| distance(position, (placements[i].0, placements[i].1)))
+    {
+        let stand = (placements[nearest].0, placements[nearest].1);
+        let (mut members, rest): (Vec<usize>, Vec<usize>) = remaining
+            .into_iter()
+            .partition(|&i| distance(stand, (placements[i].0, placements[i].1)) <= PLACE_REACH);
+        members.sort_by_key(|&i| {
+            std::cmp::Reverse(distance(stand, (placements[i].0, placements[i].1)))
+        });
+
+        clusters.push(PlaceCluster {
+            stand,
+            placements: members,
+        });
+        remaining = rest;
+        position = stand;
+    }
+
+    clusters
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_plan_clusters() {
+        // A 3x3 block near the bot and a single tile far to the right.
+        let mut placements = Vec::new();
+        for y in 0..3 {
+            for x in 0..3 {
+                placements.push((x, y, 2));
+            }
+        }
+        placements.push((20, 0, 2));
+
+        let clusters = plan_clusters(&placements, (0, 0));
+        assert_eq!(clusters.len(), 2);
+        assert_eq!(clusters[0].stand, (0, 0));
+        assert_eq!(clusters[0].placements.len(), 9);
+        assert_eq!(*clusters[0].placements.last().unwrap(), 0);
+        assert_eq!(
+            clusters[1],
+            PlaceCluster {
+                stand: (20, 0),
+                placements: vec![9],
+            }
+        );
+    }
+
+    #[test]
+    fn test_plan_clusters_empty() {
+        assert!(plan_clusters(&[], (0, 0)).is_empty());
+    }
+}
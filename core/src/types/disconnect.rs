@@ -0,0 +1,181 @@
+use std::collections::HashMap;
+
+/// Why the last session ended, as far as the bot could tell.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum DisconnectReason {
+    /// The peer dropped without the server saying why.
+    Timeout,
+    /// The connection never came up through the configured proxy.
+    ProxyError,
+    Kicked,
+    Banned,
+    Suspended,
+    /// The bot disconnected itself (logout, leave action, restart).
+    Requested,
+    /// Parked by the idle disconnect; reconnects when an action is requested.
+    Idle,
+}
+
+impl DisconnectReason {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            DisconnectReason::Timeout => "timeout",
+            DisconnectReason::ProxyError => "proxy_error",
+            DisconnectReason::Kicked => "kicked",
+            DisconnectReason::Banned => "banned",
+            DisconnectReason::Suspended => "suspended",
+            DisconnectReason::Requested => "requested",
+            DisconnectReason::Idle => "idle",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.to_lowercase().as_str() {
+            "timeout" => Some(DisconnectReason::Timeout),
+            "proxy_error" | "proxy" => Some(DisconnectReason::ProxyError),
+            "kicked" => Some(DisconnectReason::Kicked),
+            "banned" => Some(DisconnectReason::Banned),
+            "suspended" => Some(DisconnectReason::Suspended),
+            "requested" => Some(DisconnectReason::Requested),
+            "idle" => Some(DisconnectReason::Idle),
+            _ => None,
+        }
+    }
+
+    /// Classifies an account ban or suspension notice from the server. Bans
+    /// from a single world are not about the account and return `None`.
+    pub fn from_server_message(message: &str) -> Option<Self> {
+        let lower = message.to_lowercase();
+        if lower.contains("world") {
+            None
+        } else if lower.contains("suspended") {
+            Some(DisconnectReason::Suspended)
+        } else if lower.contains("banned") {
+            Some(DisconnectReason::Banned)
+        } else {
+            None
+        }
+    }
+}
+
+impl std::fmt::Display for DisconnectReason {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReconnectAction {
+    Immediate,
+    /// Waits `secs`, doubling for each consecutive backoff up to `MAX_BACKOFF_SECS`.
+    Backoff(u64),
+    Never,
+}
+
+impl ReconnectAction {
+    pub const MAX_BACKOFF_SECS: u64 = 600;
+
+    pub fn from_name(name: &str, secs: Option<u64>) -> Option<Self> {
+        match name.to_lowercase().as_str() {
+            "immediate" => Some(ReconnectAction::Immediate),
+            "backoff" => Some(ReconnectAction::Backoff(secs.unwrap_or(30))),
+            "never" => Some(ReconnectAction::Never),
+            _ => None,
+        }
+    }
+
+    /// Seconds to wait before the `streak`-th consecutive reconnect (starting at 0).
+    pub fn delay_secs(&self, streak: u32) -> u64 {
+        match self {
+            ReconnectAction::Backoff(secs) => secs
+                .saturating_mul(1u64 << streak.min(16))
+                .min(Self::MAX_BACKOFF_SECS),
+            _ => 0,
+        }
+    }
+}
+
+/// What the reconnect loop does for each disconnect reason.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ReconnectPolicy {
+    actions: HashMap<DisconnectReason, ReconnectAction>,
+}
+
+impl ReconnectPolicy {
+    pub fn action(&self, reason: DisconnectReason) -> ReconnectAction {
+        self.actions
+            .get(&reason)
+            .copied()
+            .unwrap_or(ReconnectAction::Immediate)
+    }
+
+    pub fn set(&mut self, reason: DisconnectReason, action: ReconnectAction) {
+        self.actions.insert(reason, action);
+    }
+}
+
+impl Default for ReconnectPolicy {
+    /// Reconnects right away after transient failures, backs off after kicks
+    /// and gives up on bans and suspensions.
+    fn default() -> Self {
+        let actions = HashMap::from([
+            (DisconnectReason::Timeout, ReconnectAction::Immediate),
+            (DisconnectReason::ProxyError, ReconnectAction::Immediate),
+            (DisconnectReason::Requested, ReconnectAction::Immediate),
+            (DisconnectReason::Kicked, ReconnectAction::Backoff(30)),
+            (DisconnectReason::Banned, ReconnectAction::Never),
+            (DisconnectReason::Suspended, ReconnectAction::Never),
+        ]);
+        Self { actions }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_default_policy() {
+        let mut policy = ReconnectPolicy::default();
+        assert_eq!(
+            policy.action(DisconnectReason::Timeout),
+            ReconnectAction::Immediate
+        );
+        assert_eq!(
+            policy.action(DisconnectReason::Banned),
+            ReconnectAction::Never
+        );
+
+        policy.set(DisconnectReason::Banned, ReconnectAction::Backoff(60));
+        assert_eq!(
+            policy.action(DisconnectReason::Banned),
+            ReconnectAction::Backoff(60)
+        );
+    }
+
+    #[test]
+    fn test_backoff_delay() {
+        let backoff = ReconnectAction::Backoff(30);
+        assert_eq!(backoff.delay_secs(0), 30);
+        assert_eq!(backoff.delay_secs(2), 120);
+        assert_eq!(backoff.delay_secs(10), ReconnectAction::MAX_BACKOFF_SECS);
+        assert_eq!(ReconnectAction::Immediate.delay_secs(3), 0);
+    }
+
+    #[test]
+    fn test_from_server_message() {
+        assert_eq!(
+            DisconnectReason::from_server_message("`4This account has been banned``"),
+            Some(DisconnectReason::Banned)
+        );
+        assert_eq!(
+            DisconnectReason::from_server_message("Your account is suspended for 3 days"),
+            Some(DisconnectReason::Suspended)
+        );
+        assert_eq!(DisconnectReason::from_server_message("Welcome back!"), None);
+        assert_eq!(
+            DisconnectReason::from_server_message("You've been banned from this world"),
+            None
+        );
+    }
+}
@@ -0,0 +1,9 @@
+/// A non-player object spawned into the world, such as a pet or an NPC.
+/// Tracked by net id like players, but kept apart from them.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Entity {
+    pub net_id: u32,
+    /// The `spawn` kind the server sent, e.g. `npc`.
+    pub kind: String,
+    pub position: (f32, f32),
+}
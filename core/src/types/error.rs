@@ -0,0 +1,63 @@
+use std::fmt;
+
+/// Why a bot action couldn't be carried out.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MoriError {
+    NotConnected,
+    /// The bot doesn't hold the item, or not enough of it.
+    ItemNotFound(u32),
+    PlayerNotFound(u32),
+    /// The action needs the world's main lock to belong to the bot.
+    NotWorldOwner,
+    NoTrade,
+    /// A packet or payload from the server was malformed.
+    ParseFailed(String),
+    /// A server address that doesn't parse or resolve.
+    InvalidAddress(String),
+}
+
+impl fmt::Display for MoriError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MoriError::NotConnected => write!(f, "not connected"),
+            MoriError::ItemNotFound(id) => write!(f, "not enough of item {}", id),
+            MoriError::PlayerNotFound(net_id) => {
+                write!(f, "no player with net id {} in this world", net_id)
+            }
+            MoriError::NotWorldOwner => write!(f, "the bot doesn't own this world"),
+            MoriError::NoTrade => write!(f, "no trade is open"),
+            MoriError::ParseFailed(what) => write!(f, "failed to parse {}", what),
+            MoriError::InvalidAddress(address) => {
+                write!(f, "invalid server address '{}'", address)
+            }
+        }
+    }
+}
+
+impl std::error::Error for MoriError {}
+
+impl From<MoriError> for mlua::Error {
+    fn from(error: MoriError) -> Self {
+        mlua::Error::RuntimeError(error.to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_display() {
+        assert_eq!(
+            MoriError::ItemNotFound(2).to_string(),
+            "not enough of item 2"
+        );
+        assert_eq!(
+            MoriError::ParseFailed("game packet".to_string()).to_string(),
+            "failed to parse game packet"
+        );
+
+        let lua_error: mlua::Error = MoriError::NoTrade.into();
+        assert!(lua_error.to_string().contains("no trade is open"));
+    }
+}
@@ -0,0 +1,106 @@
+/// What `Bot::farm_cycle` collects and where it deposits.
+#[derive(Debug, Clone)]
+pub struct FarmConfig {
+    /// Tile of the display box or vending machine, within reach of the bot.
+    pub container: (i32, i32),
+    /// Items moved into the container each cycle; everything held is deposited.
+    pub items: Vec<u32>,
+    pub max_cycles: u32,
+    /// Pause after collecting and after each deposit.
+    pub step_delay_ms: u64,
+}
+
+impl Default for FarmConfig {
+    fn default() -> Self {
+        Self {
+            container: (0, 0),
+            items: Vec::new(),
+            max_cycles: 50,
+            step_delay_ms: 500,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct FarmCycleStats {
+    pub collected: usize,
+    pub deposited: u32,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum FarmStop {
+    /// A cycle neither collected nor deposited anything.
+    #[default]
+    Empty,
+    Full,
+    MaxCycles,
+    /// The container is more than 4 tiles away.
+    OutOfRange,
+    /// The server never opened the deposit dialog.
+    Timeout,
+    /// The running script was asked to stop.
+    Stopped,
+}
+
+impl FarmStop {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            FarmStop::Empty => "empty",
+            FarmStop::Full => "full",
+            FarmStop::MaxCycles => "max_cycles",
+            FarmStop::OutOfRange => "out_of_range",
+            FarmStop::Timeout => "timeout",
+            FarmStop::Stopped => "stopped",
+        }
+    }
+}
+
+/// Outcome of a single deposit within a cycle.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum FarmStep {
+    Deposited,
+    /// The item ran out before it could be deposited.
+    Skipped,
+    Stop(FarmStop),
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct FarmReport {
+    pub cycles: Vec<FarmCycleStats>,
+    pub stop: FarmStop,
+}
+
+impl FarmReport {
+    pub fn collected(&self) -> usize {
+        self.cycles.iter().map(|c| c.collected).sum()
+    }
+
+    pub fn deposited(&self) -> u32 {
+        self.cycles.iter().map(|c| c.deposited).sum()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_report_totals() {
+        let report = FarmReport {
+            cycles: vec![
+                FarmCycleStats {
+                    collected: 3,
+                    deposited: 200,
+                },
+                FarmCycleStats {
+                    collected: 1,
+                    deposited: 0,
+                },
+            ],
+            stop: FarmStop::Full,
+        };
+        assert_eq!(report.collected(), 4);
+        assert_eq!(report.deposited(), 200);
+        assert_eq!(report.stop.as_str(), "full");
+    }
+}
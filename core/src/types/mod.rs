@@ -1,4 +1,9 @@
 pub mod bot;
+pub mod build;
+pub mod disconnect;
+pub mod entity;
+pub mod error;
+pub mod farm;
 pub mod flags;
 pub mod login_info;
 pub mod net_game_packet;
@@ -6,3 +11,6 @@ pub mod net_message;
 pub mod player;
 pub mod server_data;
 pub mod status;
+pub mod trade;
+pub mod warp;
+pub mod world_menu;
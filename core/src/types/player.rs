@@ -15,3 +15,71 @@ pub struct Player {
     pub country: String,
     pub position: (f32, f32),
 }
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Friend {
+    pub name: String,
+    pub online: bool,
+}
+
+impl Friend {
+    /// Parses the friends dialog, where each friend is an `add_button|friend_*|`
+    /// line whose label is green (`` `2 ``) while the friend is online.
+    pub fn parse_list(dialog: &str) -> Vec<Friend> {
+        dialog
+            .lines()
+            .filter_map(|line| {
+                let parts: Vec<&str> = line.split('|').collect();
+                if parts.len() < 3 || parts[0] != "add_button" || !parts[1].starts_with("friend_") {
+                    return None;
+                }
+                let label = parts[2];
+                let name = crate::utils::console::strip_color_codes(label)
+                    .trim()
+                    .to_string();
+                if name.is_empty() {
+                    return None;
+                }
+                Some(Friend {
+                    name,
+                    online: label.contains("`2"),
+                })
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_friend_list() {
+        let dialog = "set_default_color|`o\n\
+            add_label_with_icon|big|`wFriends``|left|1366|\n\
+            add_button|friend_1001|`2Alice``|noflags|0|0|\n\
+            add_button|friend_1002|`5Bob``|noflags|0|0|\n\
+            add_button|back|Back|noflags|0|0|\n\
+            end_dialog|friends|||\n";
+
+        let friends = Friend::parse_list(dialog);
+        assert_eq!(
+            friends,
+            vec![
+                Friend {
+                    name: "Alice".to_string(),
+                    online: true
+                },
+                Friend {
+                    name: "Bob".to_string(),
+                    online: false
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parse_empty_friend_list() {
+        assert!(Friend::parse_list("end_dialog|friends|||\n").is_empty());
+    }
+}
@@ -0,0 +1,64 @@
+/// The trade window the bot has open.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Trade {
+    pub partner_name: String,
+    pub partner_net_id: u32,
+    /// `(item_id, amount)` pairs as the server last reported each side.
+    pub our_offer: Vec<(u16, u32)>,
+    pub their_offer: Vec<(u16, u32)>,
+    pub accepted: bool,
+    pub partner_accepted: bool,
+}
+
+impl Trade {
+    pub fn new(partner_name: String, partner_net_id: u32) -> Self {
+        Self {
+            partner_name,
+            partner_net_id,
+            ..Default::default()
+        }
+    }
+}
+
+/// One side's offer from an `OnTradeStatus` body, e.g.
+/// `add_slot|242|5\nlocked|0\nreset_locks|1\naccepted|1`.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TradeStatus {
+    pub items: Vec<(u16, u32)>,
+    pub accepted: bool,
+}
+
+impl TradeStatus {
+    pub fn parse(body: &str) -> Self {
+        let mut status = TradeStatus::default();
+        for line in body.lines() {
+            let parts: Vec<&str> = line.split('|').collect();
+            match parts.as_slice() {
+                ["add_slot", id, amount, ..] => {
+                    if let (Ok(id), Ok(amount)) = (id.parse(), amount.parse()) {
+                        status.items.push((id, amount));
+                    }
+                }
+                ["accepted", value, ..] => status.accepted = *value == "1",
+                _ => {}
+            }
+        }
+        status
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_status() {
+        let status = TradeStatus::parse("add_slot|242|5\nadd_slot|2|200\nlocked|0\naccepted|1");
+        assert_eq!(status.items, vec![(242, 5), (2, 200)]);
+        assert!(status.accepted);
+
+        let empty = TradeStatus::parse("locked|0\nreset_locks|1\naccepted|0");
+        assert!(empty.items.is_empty());
+        assert!(!empty.accepted);
+    }
+}
@@ -0,0 +1,83 @@
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum WarpResult {
+    Entered(String),
+    FailedPassword,
+    WorldBusy,
+    Failed(String),
+    Timeout,
+}
+
+impl WarpResult {
+    /// Classifies the console message the server sends right before
+    /// `OnFailedToEnterWorld`.
+    pub fn from_failure_message(message: &str) -> Self {
+        let lower = message.to_lowercase();
+        if lower.contains("password") {
+            WarpResult::FailedPassword
+        } else if lower.contains("too many people") || lower.contains("busy") {
+            WarpResult::WorldBusy
+        } else {
+            WarpResult::Failed(message.to_string())
+        }
+    }
+
+    pub fn is_entered(&self) -> bool {
+        matches!(self, WarpResult::Entered(_))
+    }
+}
+
+impl std::fmt::Display for WarpResult {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            WarpResult::Entered(world) => write!(f, "Entered {}", world),
+            WarpResult::FailedPassword => write!(f, "Failed (password)"),
+            WarpResult::WorldBusy => write!(f, "World busy"),
+            WarpResult::Failed(reason) => write!(f, "Failed ({})", reason),
+            WarpResult::Timeout => write!(f, "Timeout"),
+        }
+    }
+}
+
+/// Text of a `join_request` for `world_name`. `enter` adds `enterX`/`enterY`
+/// tile coordinates; only some private servers honor them, and the official
+/// server ignores them and spawns the bot at the main door.
+pub fn join_request(world_name: &str, enter: Option<(u32, u32)>) -> String {
+    let mut request = format!("action|join_request\nname|{}\ninvitedWorld|0\n", world_name);
+    if let Some((x, y)) = enter {
+        request.push_str(&format!("enterX|{}\nenterY|{}\n", x, y));
+    }
+    request
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_from_failure_message() {
+        assert_eq!(
+            WarpResult::from_failure_message("This door requires a password"),
+            WarpResult::FailedPassword
+        );
+        assert_eq!(
+            WarpResult::from_failure_message("Too many people in that world!"),
+            WarpResult::WorldBusy
+        );
+        assert_eq!(
+            WarpResult::from_failure_message("That world is inaccessible."),
+            WarpResult::Failed("That world is inaccessible.".to_string())
+        );
+    }
+
+    #[test]
+    fn test_join_request() {
+        assert_eq!(
+            join_request("START", None),
+            "action|join_request\nname|START\ninvitedWorld|0\n"
+        );
+        assert_eq!(
+            join_request("START", Some((10, 20))),
+            "action|join_request\nname|START\ninvitedWorld|0\nenterX|10\nenterY|20\n"
+        );
+    }
+}
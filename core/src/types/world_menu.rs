@@ -0,0 +1,74 @@
+/// Worlds listed in the `OnRequestWorldSelectMenu` dialog.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct WorldMenu {
+    pub favorites: Vec<String>,
+    pub recent: Vec<String>,
+}
+
+impl WorldMenu {
+    /// Parses the menu text. Worlds are `add_floater|NAME|...` lines grouped
+    /// under `add_heading|...` lines; headings other than favorites and
+    /// recently visited (e.g. top worlds) are ignored.
+    pub fn parse(menu: &str) -> Self {
+        #[derive(PartialEq)]
+        enum Section {
+            Favorites,
+            Recent,
+            Other,
+        }
+
+        let mut result = WorldMenu::default();
+        let mut section = Section::Other;
+
+        for line in menu.lines() {
+            let mut parts = line.split('|');
+            match parts.next() {
+                Some("add_heading") => {
+                    let heading = parts.next().unwrap_or("").to_lowercase();
+                    section = if heading.contains("favorite") {
+                        Section::Favorites
+                    } else if heading.contains("recent") {
+                        Section::Recent
+                    } else {
+                        Section::Other
+                    };
+                }
+                Some("add_floater") => {
+                    let Some(name) = parts.next().filter(|name| !name.is_empty()) else {
+                        continue;
+                    };
+                    match section {
+                        Section::Favorites => result.favorites.push(name.to_string()),
+                        Section::Recent => result.recent.push(name.to_string()),
+                        Section::Other => {}
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse() {
+        let menu = "default|\n\
+            add_heading|Top Worlds<ROW2>|\n\
+            add_floater|START|0|0.5|3529161471\n\
+            add_heading|Favorites|\n\
+            add_floater|HOME|0|0.5|3529161471\n\
+            add_heading|Recently Visited Worlds<CR>|\n\
+            add_floater|FARM|0|0.5|3417414143\n\
+            add_floater|BUYDL|0|0.5|3417414143\n";
+
+        let parsed = WorldMenu::parse(menu);
+        assert_eq!(parsed.favorites, vec!["HOME"]);
+        assert_eq!(parsed.recent, vec!["FARM", "BUYDL"]);
+        assert_eq!(WorldMenu::parse(""), WorldMenu::default());
+    }
+}
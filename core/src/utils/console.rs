@@ -0,0 +1,114 @@
+/// Wait applied when the server throttles us but the duration can't be parsed.
+pub const DEFAULT_THROTTLE_SECONDS: u64 = 30;
+
+/// Removes Growtopia colour codes (a backtick followed by one character).
+pub fn strip_color_codes(message: &str) -> String {
+    let mut result = String::with_capacity(message.len());
+    let mut chars = message.chars();
+    while let Some(c) = chars.next() {
+        if c == '`' {
+            chars.next();
+        } else {
+            result.push(c);
+        }
+    }
+    result
+}
+
+/// Detects "please wait" / connection-throttle messages and returns how many
+/// seconds the server asked us to wait.
+pub fn parse_throttle(message: &str) -> Option<u64> {
+    let plain = strip_color_codes(message).to_lowercase();
+    let is_throttle = plain.contains("please wait")
+        || plain.contains("too many logins")
+        || plain.contains("too many connections");
+    if !is_throttle {
+        return None;
+    }
+
+    let words: Vec<&str> = plain.split_whitespace().collect();
+    for (i, word) in words.iter().enumerate() {
+        let digits: String = word.chars().filter(|c| c.is_ascii_digit()).collect();
+        if digits.is_empty() {
+            continue;
+        }
+        let value: u64 = match digits.parse() {
+            Ok(value) => value,
+            Err(_) => continue,
+        };
+        let unit = words.get(i + 1).copied().unwrap_or("");
+        return Some(if unit.starts_with("min") {
+            value * 60
+        } else {
+            value
+        });
+    }
+
+    Some(DEFAULT_THROTTLE_SECONDS)
+}
+
+/// Whether a console line was written by a player rather than the server:
+/// world chat and broadcasts carry a `CP:` header, and plain speech starts
+/// with `<name>`.
+pub fn is_player_chat(message: &str) -> bool {
+    let plain = strip_color_codes(message);
+    let plain = plain.trim_start();
+    plain.starts_with("CP:") || plain.contains("_CT:[") || plain.starts_with('<')
+}
+
+/// Returns the first pattern contained in `message`, ignoring case and colour codes.
+pub fn find_pattern<'a>(message: &str, patterns: &'a [String]) -> Option<&'a str> {
+    let plain = strip_color_codes(message).to_lowercase();
+    patterns
+        .iter()
+        .find(|pattern| !pattern.is_empty() && plain.contains(&pattern.to_lowercase()))
+        .map(|pattern| pattern.as_str())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_strip_color_codes() {
+        assert_eq!(strip_color_codes("`4Oops``, hi"), "Oops, hi");
+    }
+
+    #[test]
+    fn test_parse_throttle() {
+        assert_eq!(
+            parse_throttle("`4Please wait 45 seconds before logging in again."),
+            Some(45)
+        );
+        assert_eq!(
+            parse_throttle("Too many logins, please wait 2 minutes"),
+            Some(120)
+        );
+        assert_eq!(
+            parse_throttle("Please wait a bit."),
+            Some(DEFAULT_THROTTLE_SECONDS)
+        );
+        assert_eq!(parse_throttle("Welcome to Growtopia!"), None);
+    }
+
+    #[test]
+    fn test_is_player_chat() {
+        assert!(is_player_chat(
+            "CP:0_PL:4_OID:_CT:[W]_ `6<`2Mallory``>`` `$please wait 9999 minutes``"
+        ));
+        assert!(is_player_chat("`6<`2Mallory``>`` you have been kicked"));
+        assert!(!is_player_chat(
+            "`4Please wait 45 seconds before logging in again."
+        ));
+    }
+
+    #[test]
+    fn test_find_pattern() {
+        let patterns = vec!["you have been kicked".to_string(), String::new()];
+        assert_eq!(
+            find_pattern("`4You have been KICKED`` by a moderator", &patterns),
+            Some("you have been kicked")
+        );
+        assert_eq!(find_pattern("Where would you like to go?", &patterns), None);
+    }
+}
@@ -0,0 +1,143 @@
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DialogButton {
+    pub name: String,
+    pub label: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DialogInput {
+    pub name: String,
+    pub label: String,
+    pub default: String,
+    pub max_length: u32,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DialogCheckbox {
+    pub name: String,
+    pub label: String,
+    pub checked: bool,
+}
+
+/// An `OnDialogRequest` body, one `command|arg|arg...` element per line.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Dialog {
+    /// Name from `end_dialog`, echoed back as `dialog_name` when answering.
+    pub name: String,
+    pub labels: Vec<String>,
+    pub buttons: Vec<DialogButton>,
+    pub inputs: Vec<DialogInput>,
+    pub checkboxes: Vec<DialogCheckbox>,
+    /// `embed_data` pairs the server expects back unchanged.
+    pub embedded: Vec<(String, String)>,
+}
+
+impl Dialog {
+    pub fn parse(raw: &str) -> Self {
+        let mut dialog = Dialog::default();
+        for line in raw.lines() {
+            let parts: Vec<&str> = line.trim_end_matches('\r').split('|').collect();
+            let arg = |i: usize| parts.get(i).copied().unwrap_or("").to_string();
+            match parts[0] {
+                "add_label" | "add_label_with_icon" => dialog.labels.push(arg(2)),
+                "add_textbox" | "add_smalltext" => dialog.labels.push(arg(1)),
+                "add_button" => dialog.buttons.push(DialogButton {
+                    name: arg(1),
+                    label: arg(2),
+                }),
+                "add_text_input" => dialog.inputs.push(DialogInput {
+                    name: arg(1),
+                    label: arg(2),
+                    default: arg(3),
+                    max_length: arg(4).parse().unwrap_or(0),
+                }),
+                "add_checkbox" => dialog.checkboxes.push(DialogCheckbox {
+                    name: arg(1),
+                    label: arg(2),
+                    checked: arg(3) == "1",
+                }),
+                "embed_data" => dialog.embedded.push((arg(1), arg(2))),
+                "end_dialog" => dialog.name = arg(1),
+                _ => {}
+            }
+        }
+        dialog
+    }
+
+    /// Whether the dialog is a storage tile's "...is full" notice, e.g. a
+    /// storage box or display box with no room left. Only the text lines are
+    /// checked, so an item or button merely named "full" doesn't count.
+    pub fn reports_storage_full(&self) -> bool {
+        self.labels.iter().any(|label| {
+            let plain = crate::utils::console::strip_color_codes(label).to_lowercase();
+            plain.contains("is full") || plain.contains("no more room")
+        })
+    }
+}
+
+/// Body of a `dialog_return` packet (without the `action|` line), for
+/// [`Bot::send_dialog_return`](crate::Bot::send_dialog_return).
+pub fn dialog_return(name: &str, values: &[(String, String)]) -> String {
+    let mut body = format!("dialog_name|{}", name);
+    for (key, value) in values {
+        body.push_str(&format!("\n{}|{}", key, value));
+    }
+    body
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_drop_dialog() {
+        let raw = "set_default_color|`o\n\
+                   add_label_with_icon|big|`wDrop Dirt``|left|2|\n\
+                   add_textbox|How many to drop?|left|\n\
+                   add_text_input|count||200|5|\n\
+                   add_checkbox|confirm|Are you sure?|0\n\
+                   embed_data|itemID|2\n\
+                   add_button|cancel|Cancel|noflags|0|0|\n\
+                   end_dialog|drop_item|Cancel|OK|";
+        let dialog = Dialog::parse(raw);
+
+        assert_eq!(dialog.name, "drop_item");
+        assert_eq!(dialog.labels, vec!["`wDrop Dirt``", "How many to drop?"]);
+        assert_eq!(dialog.inputs[0].name, "count");
+        assert_eq!(dialog.inputs[0].default, "200");
+        assert_eq!(dialog.inputs[0].max_length, 5);
+        assert!(!dialog.checkboxes[0].checked);
+        assert_eq!(dialog.buttons[0].label, "Cancel");
+        assert_eq!(
+            dialog.embedded,
+            vec![("itemID".to_string(), "2".to_string())]
+        );
+    }
+
+    #[test]
+    fn test_reports_storage_full() {
+        let full = Dialog::parse(
+            "add_textbox|`4This Storage Box is full!``|left|\nend_dialog|storage|OK||",
+        );
+        assert!(full.reports_storage_full());
+
+        let named = Dialog::parse(
+            "add_label_with_icon|big|`wFull Moon Block``|left|2|\n\
+             add_text_input|count||1|5|\n\
+             end_dialog|storage|Cancel|OK|",
+        );
+        assert!(!named.reports_storage_full());
+    }
+
+    #[test]
+    fn test_dialog_return() {
+        let values = vec![
+            ("itemID".to_string(), "2|".to_string()),
+            ("count".to_string(), "5".to_string()),
+        ];
+        assert_eq!(
+            dialog_return("drop_item", &values),
+            "dialog_name|drop_item\nitemID|2|\ncount|5"
+        );
+    }
+}
@@ -1,3 +1,6 @@
+pub mod console;
+pub mod dialog;
 pub mod proton;
 pub mod random;
+pub mod sync;
 pub mod variant;
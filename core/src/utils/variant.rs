@@ -79,6 +79,12 @@ pub struct VariantList {
     variants: Vec<Variant>,
 }
 
+impl From<Vec<Variant>> for VariantList {
+    fn from(variants: Vec<Variant>) -> Self {
+        Self { variants }
+    }
+}
+
 impl VariantList {
     pub fn deserialize(data: &[u8]) -> Result<Self, std::io::Error> {
         let mut cursor = Cursor::new(data);
@@ -98,8 +104,7 @@ impl VariantList {
                     let len = cursor.read_u32::<LittleEndian>()? as usize;
                     let mut buffer = vec![0; len];
                     cursor.read_exact(&mut buffer)?;
-                    let value = String::from_utf8(buffer).unwrap();
-                    Variant::String(value)
+                    Variant::String(String::from_utf8_lossy(&buffer).into_owned())
                 }
                 VariantType::Vec2 => {
                     let x = cursor.read_f32::<LittleEndian>()?;
@@ -129,7 +134,153 @@ impl VariantList {
         Ok(Self { variants })
     }
 
+    /// Inverse of [`VariantList::deserialize`]. Lists longer than 255
+    /// variants don't fit the count byte and are truncated.
+    pub fn serialize(&self) -> Vec<u8> {
+        let count = self.variants.len().min(u8::MAX as usize);
+        let mut data = vec![count as u8];
+
+        for (index, variant) in self.variants.iter().take(count).enumerate() {
+            data.push(index as u8);
+            match variant {
+                Variant::Float(value) => {
+                    data.push(1);
+                    data.extend_from_slice(&value.to_le_bytes());
+                }
+                Variant::String(value) => {
+                    data.push(2);
+                    data.extend_from_slice(&(value.len() as u32).to_le_bytes());
+                    data.extend_from_slice(value.as_bytes());
+                }
+                Variant::Vec2((x, y)) => {
+                    data.push(3);
+                    data.extend_from_slice(&x.to_le_bytes());
+                    data.extend_from_slice(&y.to_le_bytes());
+                }
+                Variant::Vec3((x, y, z)) => {
+                    data.push(4);
+                    data.extend_from_slice(&x.to_le_bytes());
+                    data.extend_from_slice(&y.to_le_bytes());
+                    data.extend_from_slice(&z.to_le_bytes());
+                }
+                Variant::Unsigned(value) => {
+                    data.push(5);
+                    data.extend_from_slice(&value.to_le_bytes());
+                }
+                Variant::Signed(value) => {
+                    data.push(9);
+                    data.extend_from_slice(&value.to_le_bytes());
+                }
+                Variant::Unknown => data.push(0),
+            }
+        }
+
+        data
+    }
+
     pub fn get(&self, index: usize) -> Option<&Variant> {
         self.variants.get(index)
     }
+
+    pub fn len(&self) -> usize {
+        self.variants.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.variants.is_empty()
+    }
+
+    // Bounds-checked getters. These return `None` when the index is missing or
+    // the variant can't be coerced, so handlers can skip malformed calls.
+
+    pub fn get_string(&self, index: usize) -> Option<String> {
+        match self.get(index)? {
+            Variant::Unknown => None,
+            variant => Some(variant.as_string()),
+        }
+    }
+
+    pub fn get_i32(&self, index: usize) -> Option<i32> {
+        match self.get(index)? {
+            Variant::Signed(value) => Some(*value),
+            Variant::Unsigned(value) => Some(*value as i32),
+            Variant::Float(value) => Some(*value as i32),
+            _ => None,
+        }
+    }
+
+    pub fn get_u32(&self, index: usize) -> Option<u32> {
+        match self.get(index)? {
+            Variant::Unsigned(value) => Some(*value),
+            Variant::Signed(value) => Some(*value as u32),
+            Variant::Float(value) => Some(*value as u32),
+            _ => None,
+        }
+    }
+
+    pub fn get_vec2(&self, index: usize) -> Option<(f32, f32)> {
+        match self.get(index)? {
+            Variant::Vec2(value) => Some(*value),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn encode_string(index: u8, value: &str) -> Vec<u8> {
+        let mut data = vec![index, 2];
+        data.extend_from_slice(&(value.len() as u32).to_le_bytes());
+        data.extend_from_slice(value.as_bytes());
+        data
+    }
+
+    #[test]
+    fn test_truncated_list_getters() {
+        // "OnSendToServer" with only the port present.
+        let mut data = vec![2];
+        data.extend(encode_string(0, "OnSendToServer"));
+        data.extend([1, 9]);
+        data.extend_from_slice(&17091i32.to_le_bytes());
+
+        let variant = VariantList::deserialize(&data).unwrap();
+        assert_eq!(variant.len(), 2);
+        assert_eq!(variant.get_string(0).as_deref(), Some("OnSendToServer"));
+        assert_eq!(variant.get_i32(1), Some(17091));
+        assert_eq!(variant.get_u32(1), Some(17091));
+        assert_eq!(variant.get_i32(2), None);
+        assert_eq!(variant.get_string(4), None);
+        assert_eq!(variant.get_vec2(1), None);
+    }
+
+    #[test]
+    fn test_serialize_round_trip() {
+        let list = VariantList::from(vec![
+            Variant::String("OnSetPos".to_string()),
+            Variant::Vec2((32.0, 64.0)),
+            Variant::Vec3((1.0, 2.0, 3.0)),
+            Variant::Float(0.5),
+            Variant::Unsigned(7),
+            Variant::Signed(-3),
+        ]);
+        let data = list.serialize();
+        assert_eq!(&data[..2], &[6, 0]);
+
+        let decoded = VariantList::deserialize(&data).unwrap();
+        assert_eq!(decoded.len(), 6);
+        assert_eq!(decoded.get_string(0).as_deref(), Some("OnSetPos"));
+        assert_eq!(decoded.get_vec2(1), Some((32.0, 64.0)));
+        assert_eq!(decoded.get_u32(4), Some(7));
+        assert_eq!(decoded.get_i32(5), Some(-3));
+    }
+
+    #[test]
+    fn test_truncated_payload_errors() {
+        // Declares two variants but only carries one.
+        let mut data = vec![2];
+        data.extend(encode_string(0, "OnConsoleMessage"));
+        assert!(VariantList::deserialize(&data).is_err());
+    }
 }
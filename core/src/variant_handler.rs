@@ -1,217 +1,340 @@
+use crate::events::{BotEvent, EventType, LogLevel};
+use crate::items_sync;
 use crate::lua;
 use crate::types::bot::LuaPlayer;
+use crate::types::entity::Entity;
+use crate::types::error::MoriError;
 use crate::types::net_message::NetMessage;
-use crate::types::player::Player;
-use crate::types::status::PeerStatus;
+use crate::types::player::{Friend, Player};
+use crate::types::trade::{Trade, TradeStatus};
+use crate::types::warp::WarpResult;
+use crate::types::world_menu::WorldMenu;
 use crate::utils::proton::HashMode;
 use crate::utils::variant::VariantList;
 use crate::{Bot, utils};
 use std::collections::HashMap;
 use std::fs;
 use std::sync::Arc;
+use std::sync::atomic::Ordering;
+use std::thread;
 
 pub fn handle(bot: &Arc<Bot>, data: &[u8]) {
-    let variant = VariantList::deserialize(&data).expect("Failed to deserialize variant list");
-    let function_call: String = variant.get(0).unwrap().as_string();
+    let variant = match VariantList::deserialize(data) {
+        Ok(variant) => variant,
+        Err(e) => {
+            bot.runtime
+                .push_log(format!("Failed to deserialize variant list: {}", e));
+            return;
+        }
+    };
+    let Some(function_call) = variant.get_string(0) else {
+        return;
+    };
+
+    *bot.temporary_data.last_variant.lock().unwrap() = data.to_vec();
+
+    if bot.config.debug() && lua::has_callbacks(bot, "onRawVariant") {
+        if let Ok(bytes) = bot.scripting.lua.create_string(data) {
+            lua::invoke_callbacks(bot, "onRawVariant", (function_call, bytes));
+        }
+    }
+
+    handle_variant(bot, &variant);
+}
 
+/// Handles an already decoded variant list. Split from [`handle`] so tests can
+/// feed constructed lists.
+pub(crate) fn handle_variant(bot: &Arc<Bot>, variant: &VariantList) {
+    let Some(function_call) = variant.get_string(0) else {
+        return;
+    };
     println!("Function call: {}", function_call);
 
     // Fire onVariant callback with variant list as Lua table
     if lua::has_callbacks(bot, "onVariant") {
         let lua = &bot.scripting.lua;
-        if let Ok(table) = variant_list_to_lua_table(lua, &variant) {
+        if let Ok(table) = variant_list_to_lua_table(lua, variant) {
             lua::invoke_callbacks(bot, "onVariant", table);
         }
     }
 
-    match function_call.as_str() {
+    if dispatch(bot, &function_call, variant).is_none() {
+        bot.runtime.push_log_level(
+            LogLevel::Warning,
+            format!(
+                "Skipping malformed {} ({} arguments)",
+                function_call,
+                variant.len()
+            ),
+        );
+    }
+}
+
+/// Handles a single function call. Returns `None` when an expected argument is
+/// missing or has the wrong type.
+fn dispatch(bot: &Arc<Bot>, function_call: &str, variant: &VariantList) -> Option<()> {
+    match function_call {
         "OnSendToServer" => {
-            let port = variant.get(1).unwrap().as_int32();
-            let token = variant.get(2).unwrap().as_int32();
-            let user_id = variant.get(3).unwrap().as_int32();
-            let server_data = variant.get(4).unwrap().as_string();
+            let port = variant.get_i32(1)?;
+            let token = variant.get_i32(2)?;
+            let user_id = variant.get_i32(3)?;
+            let server_data = variant.get_string(4)?;
             let parsed_server_data: Vec<String> = server_data
                 .split('|')
                 .map(|s| s.trim_end().to_string())
                 .collect();
-            let aat = variant.get(5).unwrap().as_int32();
+            let aat = variant.get_i32(5)?;
+
+            let server = parsed_server_data.first()?.clone();
+            let door_id = parsed_server_data.get(1)?.clone();
+            let uuid = parsed_server_data.get(2)?.clone();
 
             let mut server_data_lock = bot.auth.server_data();
-            let server_data = server_data_lock.as_mut().unwrap();
+            let server_data = server_data_lock.as_mut()?;
 
-            server_data.server = parsed_server_data[0].clone();
+            server_data.server = server;
             server_data.port = port as u16;
 
             bot.runtime.set_redirecting(true);
 
             let mut login_info_lock = bot.auth.login_info();
-            let login_info = login_info_lock.as_mut().unwrap();
+            let login_info = login_info_lock.as_mut()?;
 
             login_info.token = token.to_string();
             login_info.user = user_id.to_string();
-            login_info.door_id = parsed_server_data[1].clone();
-            login_info.uuid = parsed_server_data[2].clone();
+            login_info.door_id = door_id;
+            login_info.uuid = uuid;
             login_info.aat = aat.to_string();
 
             bot.disconnect()
         }
         "OnSuperMainStartAcceptLogonHrdxs47254722215a" => {
-            let server_hash = variant.get(1).unwrap().as_uint32();
-
-            match fs::read("items.dat") {
-                Ok(data) => {
-                    let hash = utils::proton::hash(
-                        data.as_slice(),
-                        HashMode::FixedLength(data.len() as i32),
-                    ) as u32;
-
-                    if hash == server_hash {
-                        bot.send_text_packet(
-                            NetMessage::GenericText,
-                            b"action|enter_game\n",
-                        );
-                        bot.runtime.set_redirecting(false);
-                        let item_database = gtitem_r::load_from_file("items.dat")
-                            .expect("Failed to load items.dat");
-                        let mut item_database_lock = bot.world.item_database.write().unwrap();
-                        *item_database_lock = item_database;
-
-                        {
-                            let mut peer_status = bot.peer_status.lock().unwrap();
-                            *peer_status = PeerStatus::InGame;
-                        }
-
-                        return;
-                    }
-                }
-                Err(_) => {
-                    println!("Fetching server items.dat...");
-                }
-            }
-
-            bot.send_text_packet(
-                NetMessage::GenericText,
-                b"action|refresh_item_data\n",
-            );
+            let server_hash = variant.get_u32(1)?;
+            // Waiting on another bot's fetch or downloading can take a while,
+            // so do it off the network thread and keep the peer serviced.
+            let bot = Arc::clone(bot);
+            thread::spawn(move || sync_items_dat(&bot, server_hash));
         }
         "OnSetPos" => {
-            let pos = variant.get(1).unwrap().as_vec2();
+            let pos = variant.get_vec2(1)?;
             bot.movement.set_position(pos.0, pos.1);
 
             lua::invoke_callbacks(bot, "onSetPos", (pos.0, pos.1));
         }
+        "SetRespawnPos" => {
+            // Tile index (x + y * width) of the checkpoint the bot just touched.
+            let index = variant.get_u32(1)?;
+            let width = bot.world.data.lock().unwrap().width;
+            if width == 0 {
+                return None;
+            }
+            bot.world
+                .set_checkpoint(Some((index % width, index / width)));
+        }
+        "OnSetCurrentWeather" => {
+            bot.world.set_weather(variant.get_u32(1)?);
+        }
+        "OnSetBackground" => {
+            bot.world.set_background(variant.get_u32(1)?);
+        }
         "OnTalkBubble" => {
-            let net_id_val = variant.get(1).unwrap().as_int32();
-            let message = variant.get(2).unwrap().as_string();
+            let net_id_val = variant.get_i32(1)?;
+            let message = variant.get_string(2)?;
             println!("[TALK] {}", message);
 
-            lua::invoke_callbacks(bot, "onChat", (net_id_val, message.clone()));
+            // Third argument is the speaker's name, or nil if they aren't spawned.
+            let name = bot
+                .world
+                .players
+                .lock()
+                .unwrap()
+                .get(&(net_id_val as u32))
+                .map(|player| player.name.clone());
+            lua::invoke_callbacks(bot, "onChat", (net_id_val, message.clone(), name));
+            bot.events.emit(BotEvent::new(EventType::Chat {
+                net_id: net_id_val,
+                message,
+            }));
         }
         "OnConsoleMessage" => {
-            let message = variant.get(1).unwrap().as_string();
+            let message = variant.get_string(1)?;
             println!("[CONSOLE] {}", message);
+            *bot.temporary_data.last_console_message.lock().unwrap() = message.clone();
+            bot.events.emit(BotEvent::new(EventType::Console {
+                message: message.clone(),
+            }));
+            bot.handle_throttle_message(&message);
+            bot.handle_kick_message(&message);
+
+            // The first console message after entering the game is the server's greeting.
+            if bot.runtime.capture_motd(&message) {
+                lua::invoke_callbacks(bot, "onMotd", message.clone());
+            }
 
             lua::invoke_callbacks(bot, "onConsole", message);
         }
+        // OnSetBux carries the new total; OnSetBuxDelta a change to it.
         "OnSetBux" => {
-            let gems = variant.get(1).unwrap().as_int32();
-            bot.inventory.add_gems(gems);
+            let gems = variant.get_i32(1)?;
+            let previous = bot.inventory.replace_gems(gems);
+            check_gems_threshold(bot, previous);
+        }
+        "OnSetBuxDelta" => {
+            let delta = variant.get_i32(1)?;
+            let previous = bot.inventory.add_gems(delta);
+            check_gems_threshold(bot, previous);
         }
         "SetHasGrowID" => {
-            let growid = variant.get(2).unwrap().as_string();
+            let growid = variant.get_string(2)?;
             let mut login_info_lock = bot.auth.login_info();
-            let login_info = login_info_lock.as_mut().unwrap();
+            let login_info = login_info_lock.as_mut()?;
             login_info.tank_id_name = growid;
         }
         "OnRemove" => {
-            let message = variant.get(1).unwrap().as_string();
+            let message = variant.get_string(1)?;
             let data = parse_and_store_as_map(&message);
-            let net_id: u32 = data["netID"].parse().unwrap();
+            let net_id: u32 = spawn_field(&data, "netID")?;
 
             let mut players = bot.world.players.lock().unwrap();
             players.remove(&net_id);
             drop(players);
+            bot.world.entities.lock().unwrap().remove(&net_id);
 
             lua::invoke_callbacks(bot, "onPlayerLeave", net_id);
         }
         "OnSpawn" => {
-            let message = variant.get(1).unwrap().as_string();
+            let message = variant.get_string(1)?;
             let data = parse_and_store_as_map(&message);
+            let optional = |key: &str| data.get(key).cloned().unwrap_or_default();
 
             if data.contains_key("type") {
-                bot.runtime.set_net_id(
-                    data.get("netID")
-                        .unwrap()
-                        .parse()
-                        .expect("Failed to parse netid"),
-                );
-                bot.runtime.set_user_id(
-                    data.get("userID")
-                        .unwrap()
-                        .parse()
-                        .expect("Failed to parse userID"),
-                );
+                let net_id = spawn_field(&data, "netID")?;
+                let user_id = spawn_field(&data, "userID")?;
+                bot.runtime.set_net_id(net_id);
+                bot.runtime.set_user_id(user_id);
             } else {
-                let player = Player {
-                    _type: data.get("spawn").unwrap_or(&String::new()).clone(),
-                    avatar: data.get("avatar").unwrap_or(&String::new()).clone(),
-                    net_id: data["netID"].parse().expect("Failed to parse netid"),
-                    online_id: data.get("onlineID").unwrap_or(&String::new()).clone(),
-                    e_id: data["eid"].clone(),
-                    ip: data["ip"].clone(),
-                    col_rect: data["colrect"].clone(),
-                    title_icon: data.get("titleIcon").unwrap_or(&String::new()).clone(),
-                    m_state: data["mstate"].parse().expect("Failed to parse mstate"),
-                    user_id: data["userID"].parse().expect("Failed to parse userid"),
-                    invisible: data
-                        .get("invis")
-                        .unwrap_or(&"0".to_string())
-                        .parse::<u32>()
-                        .expect("Failed to parse invisible")
-                        != 0,
-                    name: data["name"].clone(),
-                    country: data["country"].clone(),
-                    position: {
-                        if data.contains_key("posXY") {
-                            let pos_xy = data
-                                .get("posXY")
-                                .unwrap()
-                                .split('|')
-                                .map(|s| {
-                                    s.trim().parse().expect("Fail to parse player coordinates")
-                                })
-                                .collect::<Vec<f32>>();
-                            (pos_xy[0], pos_xy[1])
-                        } else {
-                            (0.0, 0.0)
+                let invisible: u32 = match data.get("invis") {
+                    Some(_) => spawn_field(&data, "invis")?,
+                    None => 0,
+                };
+                let position = match data.get("posXY") {
+                    Some(pos_xy) => {
+                        let pos_xy = pos_xy
+                            .split('|')
+                            .map(|s| s.trim().parse().ok())
+                            .collect::<Option<Vec<f32>>>();
+                        match pos_xy.as_deref() {
+                            Some([x, y, ..]) => (*x, *y),
+                            _ => return None,
                         }
-                    },
+                    }
+                    None => (0.0, 0.0),
                 };
 
-                if player.m_state == 1 || player.invisible {
-                    bot.leave();
+                // Avatars spawn as `spawn|avatar`; any other kind is a pet,
+                // NPC or similar that doesn't belong in the player list.
+                if let Some(kind) = data.get("spawn").filter(|kind| *kind != "avatar") {
+                    let entity = Entity {
+                        net_id: spawn_field(&data, "netID")?,
+                        kind: kind.clone(),
+                        position,
+                    };
+                    let mut entities = bot.world.entities.lock().unwrap();
+                    entities.insert(entity.net_id, entity);
+                    return Some(());
+                }
+
+                let player = Player {
+                    _type: optional("spawn"),
+                    avatar: optional("avatar"),
+                    net_id: spawn_field(&data, "netID")?,
+                    online_id: optional("onlineID"),
+                    e_id: spawn_field(&data, "eid")?,
+                    ip: spawn_field(&data, "ip")?,
+                    col_rect: spawn_field(&data, "colrect")?,
+                    title_icon: optional("titleIcon"),
+                    m_state: spawn_field(&data, "mstate")?,
+                    user_id: spawn_field(&data, "userID")?,
+                    invisible: invisible != 0,
+                    name: spawn_field(&data, "name")?,
+                    country: spawn_field(&data, "country")?,
+                    position,
+                };
+
+                // A moderator account's own avatar spawns with mstate 1 too.
+                let own_user_id = bot.runtime.user_id();
+                let is_self = player.net_id == bot.runtime.net_id()
+                    || (own_user_id != 0 && player.user_id == own_user_id);
+
+                if !is_self {
+                    let rules = bot.config.leave_rules();
+                    if player.m_state == 1 {
+                        bot.apply_leave_action(&rules.on_mod);
+                    } else if player.invisible {
+                        bot.apply_leave_action(&rules.on_invisible);
+                    }
                 }
 
                 // Fire onPlayerJoin before inserting
-                lua::invoke_callbacks(bot, "onPlayerJoin", LuaPlayer {
-                    name: player.name.clone(),
-                    net_id: player.net_id,
-                    user_id: player.user_id,
-                    country: player.country.clone(),
-                    pos_x: player.position.0,
-                    pos_y: player.position.1,
-                    invisible: player.invisible,
-                    is_mod: player.m_state == 1,
-                });
+                lua::invoke_callbacks(bot, "onPlayerJoin", LuaPlayer::from(&player));
 
                 let mut players = bot.world.players.lock().unwrap();
                 players.insert(player.net_id, player);
             }
         }
+        "OnStartTrade" => {
+            let name = variant.get_string(1)?;
+            let net_id = variant.get_i32(2)? as u32;
+            *bot.temporary_data.trade.lock().unwrap() = Some(Trade::new(name.clone(), net_id));
+            lua::invoke_callbacks(bot, "onTradeRequest", (name, net_id));
+        }
+        "OnTradeStatus" => {
+            let net_id = variant.get_i32(1)? as u32;
+            let status = TradeStatus::parse(&variant.get_string(4)?);
+            let ours = net_id == bot.runtime.net_id();
+            {
+                let mut trade = bot.temporary_data.trade.lock().unwrap();
+                // A status for a trade we never opened isn't malformed.
+                let Some(trade) = trade.as_mut() else {
+                    return Some(());
+                };
+                if ours {
+                    trade.our_offer = status.items.clone();
+                    trade.accepted = status.accepted;
+                } else {
+                    trade.their_offer = status.items.clone();
+                    trade.partner_accepted = status.accepted;
+                }
+            }
+
+            if let Ok(offer) = trade_status_to_lua(&bot.scripting.lua, &status, ours) {
+                lua::invoke_callbacks(bot, "onTradeUpdate", offer);
+            }
+        }
+        "OnForceTradeEnd" => {
+            *bot.temporary_data.trade.lock().unwrap() = None;
+            lua::invoke_callbacks(bot, "onTradeEnd", ());
+        }
+        "OnRequestWorldSelectMenu" => {
+            let menu = variant.get_string(1)?;
+            *bot.temporary_data.world_menu.lock().unwrap() = Some(WorldMenu::parse(&menu));
+        }
+        "OnFailedToEnterWorld" => {
+            let reason = bot
+                .temporary_data
+                .last_console_message
+                .lock()
+                .unwrap()
+                .clone();
+            bot.finish_warp(WarpResult::from_failure_message(&reason));
+        }
         "OnDialogRequest" => {
-            let message = variant.get(1).unwrap().as_string();
+            let message = variant.get_string(1)?;
+            bot.handle_throttle_message(&message);
 
             lua::invoke_callbacks(bot, "onDialogRequest", message.clone());
+            lua::invoke_callbacks(bot, lua::DIALOG_HANDLER, message.clone());
+            *bot.temporary_data.last_dialog.lock().unwrap() = message.clone();
 
             let cb = {
                 let dialog_callback = bot.temporary_data.dialog_callback.lock().unwrap();
@@ -221,6 +344,20 @@ pub fn handle(bot: &Arc<Bot>, data: &[u8]) {
             if let Some(cb) = cb {
                 cb(bot);
             }
+            bot.handle_trade_dialog(&message);
+
+            if message.contains("end_dialog|socialportal")
+                && bot.temporary_data.friends_requested.load(Ordering::SeqCst)
+            {
+                bot.send_dialog_return("dialog_name|socialportal\nbuttonClicked|showfriend");
+            }
+
+            if message.contains("end_dialog|friends|") {
+                bot.temporary_data
+                    .friends_requested
+                    .store(false, Ordering::SeqCst);
+                *bot.temporary_data.friends.lock().unwrap() = Some(Friend::parse_list(&message));
+            }
 
             if message.contains("Gazette") {
                 bot.send_text_packet(
@@ -231,9 +368,122 @@ pub fn handle(bot: &Arc<Bot>, data: &[u8]) {
         }
         _ => {}
     }
+
+    Some(())
 }
 
-fn variant_list_to_lua_table(
+/// `{ mine, accepted, items = { { id, amount }, ... } }` for `onTradeUpdate`.
+fn trade_status_to_lua(
+    lua: &mlua::Lua,
+    status: &TradeStatus,
+    mine: bool,
+) -> mlua::Result<mlua::Table> {
+    let items = lua.create_table()?;
+    for &(id, amount) in &status.items {
+        let entry = lua.create_table()?;
+        entry.set("id", id)?;
+        entry.set("amount", amount)?;
+        items.push(entry)?;
+    }
+    let table = lua.create_table()?;
+    table.set("mine", mine)?;
+    table.set("accepted", status.accepted)?;
+    table.set("items", items)?;
+    Ok(table)
+}
+
+/// Fires `onGemsThreshold` once per upward crossing rather than on every gem
+/// pickup above it.
+fn check_gems_threshold(bot: &Bot, previous: i32) {
+    let total = bot.inventory.gems();
+    if let Some(threshold) = bot.config.gems_threshold() {
+        if previous < threshold && total >= threshold {
+            lua::invoke_callbacks(bot, "onGemsThreshold", total);
+        }
+    }
+}
+
+fn items_dat_hash(path: &str) -> Option<u32> {
+    let data = fs::read(path).ok()?;
+    Some(utils::proton::hash(data.as_slice(), HashMode::FixedLength(data.len() as i32)) as u32)
+}
+
+/// Makes sure `items.dat` matches `server_hash`, reusing a copy another bot
+/// is fetching or the configured mirror, then enters the game. Falls back to
+/// asking the server for the file.
+fn sync_items_dat(bot: &Arc<Bot>, server_hash: u32) {
+    let mut up_to_date = items_dat_hash("items.dat") == Some(server_hash);
+    if !up_to_date {
+        // Another bot may be fetching this version; once it's done the
+        // shared file is already current.
+        items_sync::claim(server_hash);
+        up_to_date = items_dat_hash("items.dat") == Some(server_hash);
+        if !up_to_date {
+            if let Some(url) = bot.config.items_dat_url() {
+                up_to_date = download_items_dat(bot, &url, server_hash);
+            }
+        }
+        if up_to_date {
+            items_sync::release();
+        }
+    }
+
+    if up_to_date {
+        bot.runtime.expect_motd();
+        bot.send_text_packet(NetMessage::GenericText, b"action|enter_game\n");
+        bot.runtime.set_redirecting(false);
+        match gtitem_r::load_from_file("items.dat") {
+            Ok(item_database) => *bot.world.item_database.write().unwrap() = item_database,
+            Err(_) => bot.runtime.push_log_level(
+                LogLevel::Error,
+                MoriError::ParseFailed("items.dat".to_string()).to_string(),
+            ),
+        }
+
+        bot.set_in_game();
+
+        return;
+    }
+
+    println!("Fetching server items.dat...");
+    bot.send_text_packet(NetMessage::GenericText, b"action|refresh_item_data\n");
+}
+
+/// Downloads items.dat from the configured URL and installs it as the local
+/// cache, but only if it parses and matches the hash the server expects.
+fn download_items_dat(bot: &Bot, url: &str, server_hash: u32) -> bool {
+    const DOWNLOAD_PATH: &str = "items.dat.download";
+
+    let data = match crate::server::download_items_dat(url, bot.proxy_url().as_deref()) {
+        Ok(data) => data,
+        Err(e) => {
+            bot.runtime
+                .push_log(format!("Failed to download items.dat from {}: {}", url, e));
+            return false;
+        }
+    };
+
+    if fs::write(DOWNLOAD_PATH, &data).is_err() {
+        return false;
+    }
+
+    let valid = gtitem_r::load_from_file(DOWNLOAD_PATH).is_ok()
+        && items_dat_hash(DOWNLOAD_PATH) == Some(server_hash);
+    if !valid || fs::rename(DOWNLOAD_PATH, "items.dat").is_err() {
+        bot.runtime.push_log(format!(
+            "items.dat from {} is invalid or outdated, falling back to the server copy",
+            url
+        ));
+        let _ = fs::remove_file(DOWNLOAD_PATH);
+        return false;
+    }
+
+    bot.runtime
+        .push_log(format!("Downloaded items.dat from {}", url));
+    true
+}
+
+pub(crate) fn variant_list_to_lua_table(
     lua: &mlua::Lua,
     variant: &VariantList,
 ) -> mlua::Result<mlua::Table> {
@@ -277,6 +527,13 @@ fn variant_list_to_lua_table(
     Ok(table)
 }
 
+/// Reads a required field from an `OnSpawn`/`OnRemove` body. A missing or
+/// unparseable field drops the packet, which `handle_variant` reports as
+/// malformed, instead of a panic.
+fn spawn_field<T: std::str::FromStr>(data: &HashMap<String, String>, key: &str) -> Option<T> {
+    data.get(key).and_then(|value| value.trim().parse().ok())
+}
+
 fn parse_and_store_as_map(input: &str) -> HashMap<String, String> {
     let mut map = HashMap::new();
     for line in input.lines() {
@@ -289,3 +546,154 @@ fn parse_and_store_as_map(input: &str) -> HashMap<String, String> {
     }
     map
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::bot::LoginVia;
+    use crate::utils::variant::Variant;
+    use gtitem_r::structs::ItemDatabase;
+    use std::sync::RwLock;
+
+    fn test_bot() -> Arc<Bot> {
+        let item_database = Arc::new(RwLock::new(ItemDatabase::new()));
+        Bot::new(LoginVia::default(), None, item_database, None).0
+    }
+
+    fn call(function: &str, value: i32) -> VariantList {
+        VariantList::from(vec![
+            Variant::String(function.to_string()),
+            Variant::Signed(value),
+        ])
+    }
+
+    fn spawn(net_id: u32, user_id: u32, mstate: u32) -> VariantList {
+        let message = format!(
+            "spawn|avatar\nnetID|{}\nuserID|{}\neid|1\nip|0.0.0.0\ncolrect|0|0|20|30\n\
+             posXY|0|0\nname|Tester\ncountry|us\ninvis|0\nmstate|{}\n",
+            net_id, user_id, mstate
+        );
+        VariantList::from(vec![
+            Variant::String("OnSpawn".to_string()),
+            Variant::String(message),
+        ])
+    }
+
+    #[test]
+    fn test_own_mod_spawn_does_not_leave() {
+        use crate::types::bot::{LeaveAction, LeaveRules};
+
+        let bot = test_bot();
+        bot.set_leave_rules(LeaveRules {
+            on_mod: LeaveAction::Disconnect,
+            on_invisible: LeaveAction::Ignore,
+        });
+        bot.runtime.set_net_id(5);
+        bot.runtime.set_user_id(900);
+
+        handle_variant(&bot, &spawn(5, 900, 1));
+        assert_eq!(bot.runtime.take_disconnect_reason(), None);
+        assert!(bot.world.players.lock().unwrap().contains_key(&5));
+
+        handle_variant(&bot, &spawn(6, 901, 1));
+        assert!(bot.runtime.take_disconnect_reason().is_some());
+    }
+
+    #[test]
+    fn test_malformed_spawn_is_ignored() {
+        let bot = test_bot();
+        let garbage = VariantList::from(vec![
+            Variant::String("OnSpawn".to_string()),
+            Variant::String("spawn|avatar\nnetID|abc\nname|Tester\n".to_string()),
+        ]);
+        handle_variant(&bot, &garbage);
+        assert!(bot.world.players.lock().unwrap().is_empty());
+        assert!(
+            bot.runtime
+                .logs_snapshot()
+                .iter()
+                .any(|entry| entry.level == LogLevel::Warning)
+        );
+
+        handle_variant(&bot, &spawn(7, 902, 0));
+        assert!(bot.world.players.lock().unwrap().contains_key(&7));
+
+        let remove = VariantList::from(vec![
+            Variant::String("OnRemove".to_string()),
+            Variant::String("netID|\n".to_string()),
+        ]);
+        handle_variant(&bot, &remove);
+        assert!(bot.world.players.lock().unwrap().contains_key(&7));
+    }
+
+    #[test]
+    fn test_entity_spawn_is_not_a_player() {
+        let bot = test_bot();
+        let pet = VariantList::from(vec![
+            Variant::String("OnSpawn".to_string()),
+            Variant::String("spawn|npc\nnetID|40\nposXY|64|96\n".to_string()),
+        ]);
+        handle_variant(&bot, &pet);
+        assert!(bot.world.players.lock().unwrap().is_empty());
+        assert!(
+            !bot.runtime
+                .logs_snapshot()
+                .iter()
+                .any(|entry| entry.message.contains("malformed"))
+        );
+        assert_eq!(
+            bot.world.entities.lock().unwrap().get(&40),
+            Some(&Entity {
+                net_id: 40,
+                kind: "npc".to_string(),
+                position: (64.0, 96.0),
+            })
+        );
+
+        let remove = VariantList::from(vec![
+            Variant::String("OnRemove".to_string()),
+            Variant::String("netID|40\n".to_string()),
+        ]);
+        handle_variant(&bot, &remove);
+        assert!(bot.world.entities.lock().unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_set_bux_is_absolute() {
+        let bot = test_bot();
+        handle_variant(&bot, &call("OnSetBux", 500));
+        handle_variant(&bot, &call("OnSetBux", 750));
+        assert_eq!(bot.inventory.gems(), 750);
+    }
+
+    #[test]
+    fn test_set_bux_delta_accumulates() {
+        let bot = test_bot();
+        handle_variant(&bot, &call("OnSetBux", 100));
+        handle_variant(&bot, &call("OnSetBuxDelta", 25));
+        handle_variant(&bot, &call("OnSetBuxDelta", -5));
+        assert_eq!(bot.inventory.gems(), 120);
+    }
+
+    #[test]
+    fn test_gems_clamp_at_zero() {
+        let bot = test_bot();
+        handle_variant(&bot, &call("OnSetBux", -10));
+        assert_eq!(bot.inventory.gems(), 0);
+
+        handle_variant(&bot, &call("OnSetBux", 30));
+        handle_variant(&bot, &call("OnSetBuxDelta", -100));
+        assert_eq!(bot.inventory.gems(), 0);
+    }
+
+    #[test]
+    fn test_malformed_set_bux_is_ignored() {
+        let bot = test_bot();
+        handle_variant(&bot, &call("OnSetBux", 40));
+        handle_variant(
+            &bot,
+            &VariantList::from(vec![Variant::String("OnSetBux".into())]),
+        );
+        assert_eq!(bot.inventory.gems(), 40);
+    }
+}
@@ -1,4 +1,4 @@
-use std::sync::{Arc, RwLock};
+use std::sync::{Arc, Mutex, RwLock};
 
 use crate::gui::add_bot_dialog::AddBotDialog;
 use crate::manager::bot_manager::BotManager;
@@ -11,6 +11,8 @@ pub struct BotMenu {
     pub selected_bot: String,
     pub warp_name: String,
     pub current_menu: String,
+    /// Outcome of the last GUI warp, filled in by the warp thread.
+    pub warp_status: Arc<Mutex<Option<String>>>,
 }
 
 impl BotMenu {
@@ -182,6 +184,7 @@ impl BotMenu {
                                                     let enet_status = bot.enet_status();
                                                     let peer_status = bot.peer_status();
                                                     let ping = bot.runtime.ping();
+                                                    let gravity = bot.movement.gravity();
                                                     let world_name = {
                                                         bot.world.data.lock().unwrap().name.clone()
                                                     };
@@ -211,6 +214,9 @@ impl BotMenu {
                                                     ui.label("Ping");
                                                     ui.label(ping.to_string());
                                                     ui.end_row();
+                                                    ui.label("Gravity");
+                                                    ui.label(gravity.to_string());
+                                                    ui.end_row();
                                                     ui.label("World");
                                                     ui.label(world_name);
                                                     ui.end_row();
@@ -249,12 +255,38 @@ impl BotMenu {
                                                 if ui.button("Warp").clicked() {
                                                     let bot_clone = bot.clone();
                                                     let world_name = self.warp_name.clone();
+                                                    let warp_status = Arc::clone(&self.warp_status);
+                                                    *warp_status.lock().unwrap() = Some(format!(
+                                                        "Warping to {}...",
+                                                        world_name
+                                                    ));
                                                     std::thread::spawn(move || {
-                                                        bot_clone.warp(world_name);
+                                                        let timeout_ms =
+                                                            bot_clone.config.world_join_timeout();
+                                                        let timeout =
+                                                            std::time::Duration::from_millis(
+                                                                timeout_ms as u64,
+                                                            );
+                                                        let result = bot_clone.warp_and_wait(
+                                                            gt_core::ActionSource::Api,
+                                                            world_name.clone(),
+                                                            timeout,
+                                                        );
+                                                        let status = format!(
+                                                            "Warp to {}: {}",
+                                                            world_name, result
+                                                        );
+                                                        bot_clone.runtime.push_log(status.clone());
+                                                        *warp_status.lock().unwrap() = Some(status);
                                                     });
                                                 }
                                             },
                                         );
+                                        if let Some(status) =
+                                            self.warp_status.lock().unwrap().as_ref()
+                                        {
+                                            ui.label(status);
+                                        }
                                     });
                                     ui.allocate_space(egui::vec2(ui.available_width(), 5.0));
                                     ui.group(|ui| {
@@ -408,7 +440,7 @@ impl BotMenu {
 
                                         ui.vertical(|ui| {
                                             for log in logs.iter() {
-                                                ui.label(log);
+                                                ui.label(log.to_string());
                                             }
                                             ui.add_space(10.0);
                                         });
@@ -6,11 +6,25 @@ use std::sync::{Arc, RwLock};
 pub struct ItemDatabase {
     search_query: String,
     selected_item_index: Option<u32>,
+    reload_status: Option<String>,
 }
 
 impl ItemDatabase {
     pub fn render(&mut self, ui: &mut Ui, manager: &Arc<RwLock<BotManager>>, _ctx: &egui::Context) {
         let manager = manager.read().unwrap();
+
+        ui.horizontal(|ui| {
+            if ui.button("Reload items.dat").clicked() {
+                self.reload_status = Some(match manager.reload_items_database() {
+                    Ok(count) => format!("Loaded {} items", count),
+                    Err(e) => e,
+                });
+            }
+            if let Some(status) = &self.reload_status {
+                ui.label(status);
+            }
+        });
+
         let items_database = manager.items_database.read().unwrap();
     }
 }
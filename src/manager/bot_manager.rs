@@ -29,6 +29,17 @@ impl BotManager {
 }
 
 impl BotManager {
+    /// Re-reads items.dat and swaps it into the shared database. Every bot
+    /// holds the same `Arc`, so live bots see the new items immediately.
+    /// Returns the new item count; on failure the old database is kept.
+    pub fn reload_items_database(&self) -> Result<usize, String> {
+        let item_database = load_from_file("items.dat")
+            .map_err(|e| format!("failed to load items.dat: {:?}", e))?;
+        let item_count = item_database.items.len();
+        *self.items_database.write().unwrap() = item_database;
+        Ok(item_count)
+    }
+
     pub fn add_bot(manager: Arc<RwLock<Self>>, bot: BotConfig) {
         std::thread::spawn(move || {
             let items_database = {